@@ -0,0 +1,49 @@
+//! Symbolize addresses like `eu-addr2line -f -i -C`.
+//!
+//! Usage: `addr2line [-e FILE | -p PID] ADDR...`
+
+use libdwfl::{Dwfl, Symbolizer};
+
+use std::env;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let (dwfl, bias) = match args.next().as_deref() {
+        Some("-e") => {
+            // Offline files may be loaded at a bias, but we take addresses
+            // from the file itself.
+            let dwfl = Dwfl::open(args.next().ok_or("-e needs a file")?)?;
+            let bias = match dwfl.modules().next() {
+                Some(module) => module?.bias()?,
+                None => 0,
+            };
+            (dwfl, bias)
+        }
+        Some("-p") => {
+            let pid = args.next().ok_or("-p needs a pid")?.parse()?;
+            (Dwfl::from_pid(pid)?, 0)
+        }
+        _ => return Err("usage: addr2line [-e FILE | -p PID] ADDR...".into()),
+    };
+
+    let mut symbolizer = Symbolizer::new(&dwfl);
+    for arg in args {
+        let address = u64::from_str_radix(arg.trim_start_matches("0x"), 16)?;
+        let frames = symbolizer.symbolize(address.wrapping_add(bias))?;
+        if frames.is_empty() {
+            println!("??\n??:0");
+        }
+        for frame in frames {
+            println!("{}", frame.function().unwrap_or("??"));
+            match frame.file() {
+                Some(file) if frame.column() != 0 => {
+                    println!("{}:{}:{}", file.display(), frame.line(), frame.column())
+                }
+                Some(file) => println!("{}:{}", file.display(), frame.line()),
+                None => println!("??:0"),
+            }
+        }
+    }
+    Ok(())
+}
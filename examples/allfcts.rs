@@ -5,8 +5,8 @@ use std::error::Error;
 
 fn main() -> Result<(), Box<dyn Error>> {
     for arg in env::args_os().skip(1) {
-        let dw = libdw::Dwarf::open(arg)?;
-        // TODO setup_alt
+        let mut dw = libdw::Dwarf::open(arg)?;
+        dw.setup_alt()?;
 
         for cu in dw.compile_units() {
             let die = cu?.get_die()?;
@@ -10,7 +10,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         for cu in dw.compile_units() {
             let die = cu?.get_die()?;
 
-            if let Ok(attr) = die.attr(libdw::raw::DW_AT_producer) {
+            if let Ok(attr) = die.attr(libdw::DwAt::PRODUCER) {
                 if let Ok(s) = attr.get_string() {
                     println!("{:?}", s);
                 }
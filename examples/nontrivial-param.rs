@@ -3,7 +3,7 @@
 //! [1]: https://github.com/cuviper/nontrivial-param
 
 use cpp_demangle::Symbol;
-use libdw::{raw, Die};
+use libdw::{Die, DwAt, DwTag};
 use libdwfl::Dwfl;
 
 use std::borrow::Cow;
@@ -15,9 +15,9 @@ fn main() -> Result<(), Box<dyn Error>> {
     for arg in env::args_os().skip(1) {
         let dwfl = Dwfl::open(arg)?;
         for dw in dwfl.dwarfs() {
-            for cu in dw?.compile_units() {
+            for cu in dw?.dwarf().compile_units() {
                 let die = cu?.get_die()?;
-                if let Ok(raw::DW_TAG_compile_unit) = die.tag() {
+                if let Ok(DwTag::COMPILE_UNIT) = die.tag() {
                     die.for_each_func(process_function)?;
                 }
             }
@@ -35,7 +35,7 @@ fn process_function(function: &Die<'_>) -> libdw::Result<bool> {
     let mut printed_function_name = false;
     for child in function.iter_children() {
         let child = child?;
-        if let Ok(raw::DW_TAG_formal_parameter) = child.tag() {
+        if let Ok(DwTag::FORMAL_PARAMETER) = child.tag() {
             if has_nontrivial_type(&child) {
                 if !printed_function_name {
                     eprintln!("{:?}: In function {:?}:", file, function_name(function));
@@ -61,22 +61,18 @@ fn in_system_header(file: &CStr) -> bool {
 }
 
 fn has_nontrivial_type(die: &Die<'_>) -> bool {
-    die.attr(raw::DW_AT_type)
+    die.attr(DwAt::TYPE)
         .and_then(|attr| attr.get_die())
-        .map(|ty| match ty.tag() {
-            Ok(raw::DW_TAG_class_type) | Ok(raw::DW_TAG_structure_type) => true,
-            Ok(raw::DW_TAG_const_type)
-            | Ok(raw::DW_TAG_volatile_type)
-            | Ok(raw::DW_TAG_typedef) => has_nontrivial_type(&ty),
-            _ => false,
-        })
+        .and_then(|ty| ty.peel_type())
+        .and_then(|ty| ty.tag())
+        .map(|tag| tag == DwTag::CLASS_TYPE || tag == DwTag::STRUCTURE_TYPE)
         .unwrap_or(false)
 }
 
 fn function_name<'dw>(function: &'dw Die<'_>) -> Cow<'dw, CStr> {
     let linkage_name = function
-        .attr_integrate(raw::DW_AT_linkage_name)
-        .or_else(|_| function.attr_integrate(raw::DW_AT_MIPS_linkage_name))
+        .attr_integrate(DwAt::LINKAGE_NAME)
+        .or_else(|_| function.attr_integrate(DwAt::MIPS_LINKAGE_NAME))
         .and_then(|attr| attr.get_string());
 
     if let Ok(name) = linkage_name {
@@ -0,0 +1,140 @@
+//! Print structure layouts, like a simplified `pahole`.
+//!
+//! Usage: pahole FILE [STRUCT...]
+
+use libdw::layout::Layout;
+use libdw::{Die, DwAt, DwTag};
+
+use std::env;
+use std::error::Error;
+
+const CACHELINE_SIZE: u64 = 64;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args_os().skip(1);
+    let path = args.next().ok_or("usage: pahole FILE [STRUCT...]")?;
+    let names: Vec<_> = args.map(|s| s.to_string_lossy().into_owned()).collect();
+
+    let dw = libdw::Dwarf::open(path)?;
+    for cu in dw.compile_units() {
+        let die = cu?.get_die()?;
+        visit(&die, &names)?;
+    }
+    Ok(())
+}
+
+fn visit(die: &Die<'_>, names: &[String]) -> libdw::Result<()> {
+    for child in die.iter_children() {
+        let child = child?;
+        match child.tag()? {
+            DwTag::STRUCTURE_TYPE | DwTag::CLASS_TYPE | DwTag::UNION_TYPE => {
+                if !child.has_attr(DwAt::DECLARATION)? {
+                    if let Ok(name) = child.type_name() {
+                        if names.is_empty() || names.contains(&name) {
+                            match Layout::new(&child) {
+                                Ok(layout) => print_layout(&layout, &name),
+                                Err(e) => eprintln!("{}: {}", name, e),
+                            }
+                        }
+                    }
+                }
+                visit(&child, names)?;
+            }
+            DwTag::NAMESPACE => visit(&child, names)?,
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+fn print_layout(layout: &Layout<'_>, name: &str) {
+    println!("{} {{", name);
+
+    let boundaries = layout.cacheline_boundaries(CACHELINE_SIZE);
+    for (index, member) in layout.members().iter().enumerate() {
+        if let Some(hole) = layout.hole_before(index) {
+            print_hole(hole.bit_offset(), hole.bit_size());
+        }
+        if let Some(&(_, line)) = boundaries.iter().find(|&&(i, _)| i == index) {
+            println!(
+                "\t/* --- cacheline {} boundary ({} bytes) --- */",
+                line,
+                line * CACHELINE_SIZE
+            );
+        }
+
+        let ty = member
+            .type_die()
+            .and_then(|ty| ty.type_name().ok())
+            .unwrap_or_else(|| "?".into());
+        let name = member
+            .name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let decl = if member.is_inheritance() {
+            format!("{} /* base */", ty)
+        } else if member.is_bitfield() {
+            format!("{} {}:{}", ty, name, member.bit_size())
+        } else {
+            format!("{} {}", ty, name)
+        };
+
+        if member.is_bitfield() {
+            println!(
+                "\t{:<40} /* {:5}:{:2} {:4} */",
+                decl + ";",
+                member.offset(),
+                member.bit_offset() % 8,
+                member.size()
+            );
+        } else {
+            println!(
+                "\t{:<40} /* {:5}    {:4} */",
+                decl + ";",
+                member.offset(),
+                member.size()
+            );
+        }
+    }
+
+    let padding = layout.padding_bits();
+    let holes: Vec<_> = layout.holes().collect();
+    let hole_bits: u64 = holes.iter().map(|hole| hole.bit_size()).sum();
+    println!();
+    println!(
+        "\t/* size: {}, cachelines: {}, members: {} */",
+        layout.size(),
+        layout.cachelines(CACHELINE_SIZE),
+        layout.members().len()
+    );
+    if !holes.is_empty() {
+        println!(
+            "\t/* sum members: {}, holes: {}, sum holes: {} */",
+            layout.size() - (hole_bits + padding) / 8,
+            holes.len(),
+            hole_bits / 8
+        );
+    }
+    if padding > 0 && !layout.is_union() {
+        println!("\t/* padding: {} */", padding / 8);
+    }
+    println!("}};");
+    println!();
+}
+
+fn print_hole(bit_offset: u64, bit_size: u64) {
+    if bit_size.is_multiple_of(8) {
+        println!(
+            "\t/* XXX {} byte hole at offset {}, try to pack */",
+            bit_size / 8,
+            bit_offset / 8
+        );
+    } else {
+        println!(
+            "\t/* XXX {} bit hole at offset {}:{}, try to pack */",
+            bit_size,
+            bit_offset / 8,
+            bit_offset % 8
+        );
+    }
+}
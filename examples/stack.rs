@@ -0,0 +1,74 @@
+//! Print the stacks of each thread, like `eu-stack -i`.
+//!
+//! Usage: `stack -p PID | --core FILE [-e EXE]`
+
+use libdwfl::{Dwfl, Symbolizer};
+
+use std::env;
+use std::error::Error;
+use std::path::PathBuf;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut pid = None;
+    let mut core = None;
+    let mut exe = None;
+    let mut args = env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("-p") => pid = Some(args.next().ok_or("-p needs a pid")?),
+            Some("--core") => core = Some(args.next().ok_or("--core needs a file")?),
+            Some("-e") => exe = Some(PathBuf::from(args.next().ok_or("-e needs a file")?)),
+            _ => return Err(format!("unknown argument {:?}", arg).into()),
+        }
+    }
+
+    match (pid, core) {
+        (Some(pid), None) => {
+            let pid = pid.to_str().ok_or("bad pid")?.parse()?;
+            let mut dwfl = Dwfl::from_pid(pid)?;
+            dwfl.attach_pid(pid, false)?;
+            print_stacks(&dwfl)
+        }
+        (None, Some(core)) => {
+            let core = libelf::Elf::open(core)?;
+            let mut dwfl = Dwfl::from_core(&core, exe.as_deref())?;
+            dwfl.attach_core(&core)?;
+            print_stacks(&dwfl)
+        }
+        _ => Err("usage: stack -p PID | --core FILE [-e EXE]".into()),
+    }
+}
+
+fn print_stacks(dwfl: &Dwfl<'_>) -> Result<(), Box<dyn Error>> {
+    let mut symbolizer = Symbolizer::new(dwfl);
+    println!("PID {}", dwfl.pid().unwrap_or(0));
+    for tid in dwfl.threads()? {
+        println!("TID {}:", tid);
+        let mut index = 0;
+        let result = dwfl.for_each_thread_frame(tid, |frame| {
+            let pc = frame.pc()?;
+            let frames = symbolizer.symbolize(frame.lookup_pc()?).unwrap_or_default();
+            if frames.is_empty() {
+                println!("#{:<2} {:#018x} ??", index, pc);
+            }
+            for (i, f) in frames.iter().enumerate() {
+                // Only the concrete function gets the frame's address.
+                let name = f.function().unwrap_or("??");
+                if i + 1 < frames.len() {
+                    println!("#{:<2} {:18} {}", index, "", name);
+                } else {
+                    println!("#{:<2} {:#018x} {}", index, pc, name);
+                }
+                if let Some(file) = f.file() {
+                    println!("    {}:{}", file.display(), f.line());
+                }
+            }
+            index += 1;
+            Ok(true)
+        });
+        if let Err(e) = result {
+            println!("    {}", e);
+        }
+    }
+    Ok(())
+}
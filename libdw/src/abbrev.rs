@@ -0,0 +1,395 @@
+use crate::ffi;
+
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr;
+use std::rc::Rc;
+
+use super::{Die, Dwarf, Error, Result};
+use super::{DwAt, DwForm, DwTag};
+
+/// An abbreviation, describing the tag, children, and attribute forms shared
+/// by any number of DIEs in a unit.
+#[derive(Clone)]
+pub struct Abbrev<'dw> {
+    inner: *mut ffi::Dwarf_Abbrev,
+    // Abbreviations read by offset are filled into our own storage.
+    _storage: Option<Rc<AbbrevStorage>>,
+    phantom: PhantomData<&'dw Dwarf<'dw>>,
+}
+
+/// Space for libdw's private `struct Dwarf_Abbrev`, which `dwarf_offabbrev`
+/// fills in but gives no way to allocate.
+///
+/// As of elfutils 0.188, the struct is packed into 28 bytes: an offset, the
+/// attribute pointer, a bit-field of `has_children` and `code`, the tag and
+/// the attribute count.  This reserves 64 bytes, aligned for the offset and
+/// pointer, so the layout may more than double before it overflows.
+type AbbrevStorage = UnsafeCell<[u64; 8]>;
+
+/// One attribute specification within an `Abbrev`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AbbrevAttr {
+    name: DwAt,
+    form: DwForm,
+    implicit_const: i64,
+    offset: u64,
+}
+
+impl<'dw> Abbrev<'dw> {
+    #[inline]
+    pub(crate) fn new(abbrev: *mut ffi::Dwarf_Abbrev) -> Abbrev<'dw> {
+        Abbrev {
+            inner: abbrev,
+            _storage: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Read the abbreviation at `offset` in `.debug_abbrev`, with its length,
+    /// or `None` for the null entry that ends a table.
+    fn read(dwarf: &Dwarf<'dw>, offset: u64) -> Result<(Option<Abbrev<'dw>>, u64)> {
+        // SAFETY: libdw writes one `Dwarf_Abbrev`, which fits in
+        // `AbbrevStorage` as checked against elfutils 0.188; see its comment.
+        let storage = Rc::new(UnsafeCell::new([0; 8]));
+        let inner = storage.get() as *mut ffi::Dwarf_Abbrev;
+        let mut length = 0;
+        let rc = ffi!(dwarf_offabbrev(dwarf.as_ptr(), offset, &mut length, inner))?;
+        if rc != 0 {
+            // libdw doesn't set the length of the null entry, a single zero.
+            return Ok((None, 1));
+        }
+        let abbrev = Abbrev {
+            inner,
+            _storage: Some(storage),
+            phantom: PhantomData,
+        };
+        Ok((Some(abbrev), length as u64))
+    }
+
+    /// Get the code that DIEs use to refer to this abbreviation.
+    #[inline]
+    pub fn code(&self) -> u32 {
+        raw_ffi!(dwarf_getabbrevcode(self.as_ptr()))
+    }
+
+    #[inline]
+    pub fn tag(&self) -> DwTag {
+        DwTag(raw_ffi!(dwarf_getabbrevtag(self.as_ptr())) as u16)
+    }
+
+    #[inline]
+    pub fn has_children(&self) -> bool {
+        raw_ffi!(dwarf_abbrevhaschildren(self.as_ptr())) > 0
+    }
+
+    /// Get the number of attribute specifications.
+    #[inline]
+    pub fn attr_count(&self) -> Result<usize> {
+        let mut count = 0;
+        ffi!(dwarf_getattrcnt(self.as_ptr(), &mut count))?;
+        Ok(count)
+    }
+
+    /// Get just the name and form of the attribute specification at `index`,
+    /// if in range.
+    pub fn attr_name_form(&self, index: usize) -> Option<(DwAt, DwForm)> {
+        let mut name = 0;
+        let mut form = 0;
+        let rc = raw_ffi!(dwarf_getabbrevattr(
+            self.as_ptr(),
+            index,
+            &mut name,
+            &mut form,
+            ptr::null_mut()
+        ));
+        if rc != 0 {
+            return None;
+        }
+        Some((DwAt(name as u16), DwForm(form as u16)))
+    }
+
+    /// Get the attribute specification at `index`, if in range.
+    pub fn attr(&self, index: usize) -> Option<AbbrevAttr> {
+        let mut name = 0;
+        let mut form = 0;
+        let mut data = 0;
+        let mut offset = 0;
+        let rc = raw_ffi!(dwarf_getabbrevattr_data(
+            self.as_ptr(),
+            index,
+            &mut name,
+            &mut form,
+            &mut data,
+            &mut offset
+        ));
+        if rc != 0 {
+            return None;
+        }
+        Some(AbbrevAttr {
+            name: DwAt(name as u16),
+            form: DwForm(form as u16),
+            implicit_const: data,
+            offset,
+        })
+    }
+
+    /// Get all of the attribute specifications, in order.
+    pub fn attrs(&self) -> Vec<AbbrevAttr> {
+        (0..).map_while(|i| self.attr(i)).collect()
+    }
+
+    #[inline]
+    pub fn as_ptr(&self) -> *mut ffi::Dwarf_Abbrev {
+        self.inner
+    }
+}
+
+impl<'dw> fmt::Debug for Abbrev<'dw> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = fmt.debug_struct("Abbrev");
+        debug
+            .field("code", &self.code())
+            .field("tag", &self.tag())
+            .field("has_children", &self.has_children());
+        debug.field("attrs", &self.attrs());
+        debug.finish()
+    }
+}
+
+impl AbbrevAttr {
+    #[inline]
+    pub fn name(&self) -> DwAt {
+        self.name
+    }
+
+    #[inline]
+    pub fn form(&self) -> DwForm {
+        self.form
+    }
+
+    /// Get the value stored in the abbreviation for `DW_FORM_implicit_const`.
+    #[inline]
+    pub fn implicit_const(&self) -> Option<i64> {
+        if self.form == DwForm::IMPLICIT_CONST {
+            Some(self.implicit_const)
+        } else {
+            None
+        }
+    }
+
+    /// Get the offset of this specification, as libdw counts it: the offset of
+    /// the abbreviation in `.debug_abbrev`, plus the distance from its first
+    /// attribute specification.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+/// An iterator over the abbreviation table of a unit.
+#[derive(Debug)]
+pub struct Abbrevs<'dw> {
+    die: Die<'dw>,
+    offset: u64,
+    finished: bool,
+}
+
+impl<'dw> Abbrevs<'dw> {
+    #[inline]
+    pub(crate) fn new(die: &Die<'dw>) -> Abbrevs<'dw> {
+        Abbrevs {
+            die: die.clone(),
+            offset: 0,
+            finished: false,
+        }
+    }
+}
+
+impl<'dw> Iterator for Abbrevs<'dw> {
+    type Item = Result<Abbrev<'dw>>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        // Offsets are relative to the start of the unit's table.
+        let mut length = 0;
+        let abbrev = raw_ffi!(dwarf_getabbrev(self.die.as_ptr(), self.offset, &mut length));
+
+        if abbrev.is_null() {
+            self.finished = true;
+            Some(Err(Error::last()))
+        } else if abbrev as isize == -1 {
+            // libdw's DWARF_END_ABBREV marks the terminating null entry.
+            self.finished = true;
+            None
+        } else {
+            self.offset += length as u64;
+            Some(Ok(Abbrev::new(abbrev)))
+        }
+    }
+}
+
+/// An iterator over the abbreviation table at a given offset in
+/// `.debug_abbrev`, independent of any unit.
+#[derive(Debug)]
+pub struct OffsetAbbrevs<'dw> {
+    dwarf: &'dw Dwarf<'dw>,
+    offset: u64,
+    finished: bool,
+}
+
+impl<'dw> OffsetAbbrevs<'dw> {
+    #[inline]
+    pub(crate) fn new(dwarf: &'dw Dwarf<'dw>, offset: u64) -> OffsetAbbrevs<'dw> {
+        OffsetAbbrevs {
+            dwarf,
+            offset,
+            finished: false,
+        }
+    }
+
+    /// Get the offset of the next abbreviation.
+    ///
+    /// Once the iterator is exhausted, this is just past the table's null
+    /// entry, where the next table may start.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl<'dw> Iterator for OffsetAbbrevs<'dw> {
+    type Item = Result<Abbrev<'dw>>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match Abbrev::read(self.dwarf, self.offset) {
+            Ok((abbrev, length)) => {
+                self.offset += length;
+                if abbrev.is_none() {
+                    self.finished = true;
+                }
+                abbrev.map(Ok)
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Dwarf;
+    use std::collections::HashMap;
+
+    #[test]
+    fn die_abbrevs() {
+        let exe = std::env::current_exe().unwrap();
+        let dw = Dwarf::open(exe).unwrap();
+        let cu = dw.compile_units().next().unwrap().unwrap();
+
+        let mut table = HashMap::new();
+        for abbrev in cu.abbrevs().unwrap() {
+            let abbrev = abbrev.unwrap();
+            assert!(table.insert(abbrev.code(), abbrev).is_none());
+        }
+        assert!(!table.is_empty());
+
+        for die in cu.tree().unwrap() {
+            let die = die.unwrap();
+            let abbrev = die.abbrev().unwrap();
+            assert_eq!(abbrev.as_ptr(), table[&abbrev.code()].as_ptr());
+            assert_eq!(abbrev.tag(), die.tag().unwrap());
+            assert_eq!(abbrev.has_children(), die.has_children().unwrap());
+
+            let names: Vec<_> = abbrev.attrs().iter().map(|a| a.name()).collect();
+            let attrs: Vec<_> = die.attrs().unwrap().iter().map(|a| a.name()).collect();
+            assert_eq!(names, attrs);
+            assert_eq!(abbrev.attr_count().unwrap(), die.attr_count().unwrap());
+            assert_eq!(abbrev.attr_count().unwrap(), names.len());
+        }
+    }
+
+    fn uleb(data: &[u8], offset: &mut usize) -> u64 {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = data[*offset];
+            *offset += 1;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return value;
+            }
+            shift += 7;
+        }
+    }
+
+    #[test]
+    fn offset_abbrevs() {
+        use crate::lists::section_data;
+        use crate::{DwAt, DwForm, DwTag};
+
+        let exe = std::env::current_exe().unwrap();
+        let dw = Dwarf::open(exe).unwrap();
+        let data = section_data(dw.as_ptr(), ".debug_abbrev").unwrap().unwrap();
+
+        // Walk every table in the section, decoding it by hand alongside.
+        let mut offset = 0;
+        let mut tables = 0;
+        while offset < data.len() {
+            let mut abbrevs = dw.abbrevs_at(offset as u64);
+            for abbrev in &mut abbrevs {
+                let abbrev = abbrev.unwrap();
+                assert_eq!(u64::from(abbrev.code()), uleb(data, &mut offset));
+                assert_eq!(abbrev.tag(), DwTag(uleb(data, &mut offset) as u16));
+                assert_eq!(abbrev.has_children(), data[offset] != 0);
+                offset += 1;
+
+                let attrs = abbrev.attrs();
+                assert_eq!(attrs.len(), abbrev.attr_count().unwrap());
+                let base = offset as u64 - attrs.first().map_or(0, |a| a.offset());
+                for (i, attr) in attrs.iter().enumerate() {
+                    assert_eq!(base + attr.offset(), offset as u64);
+                    assert_eq!(attr.name(), DwAt(uleb(data, &mut offset) as u16));
+                    assert_eq!(attr.form(), DwForm(uleb(data, &mut offset) as u16));
+                    if attr.form() == DwForm::IMPLICIT_CONST {
+                        // The value is checked by libdw; just skip the SLEB128.
+                        uleb(data, &mut offset);
+                    }
+                    let name_form = abbrev.attr_name_form(i).unwrap();
+                    assert_eq!(name_form, (attr.name(), attr.form()));
+                }
+                assert_eq!(&data[offset..offset + 2], &[0, 0]);
+                offset += 2;
+            }
+            // Skip the table's null entry.
+            assert_eq!(data[offset], 0);
+            offset += 1;
+            assert_eq!(abbrevs.offset(), offset as u64);
+            tables += 1;
+        }
+        assert!(tables > 0);
+
+        // Each unit's table matches the one read through its DIEs.
+        for unit in dw.compile_units() {
+            let unit = unit.unwrap();
+            let codes: Vec<_> = dw
+                .abbrevs_at(unit.header().unwrap().abbrev_offset())
+                .map(|a| a.unwrap().code())
+                .collect();
+            let expected: Vec<_> = unit.abbrevs().unwrap().map(|a| a.unwrap().code()).collect();
+            assert_eq!(codes, expected);
+        }
+    }
+}
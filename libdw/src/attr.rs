@@ -3,6 +3,7 @@ use crate::ffi;
 use std::cell::UnsafeCell;
 use std::ffi::CStr;
 use std::fmt;
+use std::io;
 use std::marker::PhantomData;
 use std::ptr;
 use std::slice;
@@ -10,6 +11,8 @@ use std::slice;
 use super::Die;
 use super::Dwarf;
 use super::Result;
+use super::{DwAt, DwForm};
+use super::{RawLocationList, RawRangeList};
 
 #[repr(transparent)]
 pub struct Attribute<'dw> {
@@ -22,12 +25,56 @@ pub enum AttributeValue<'dw> {
     String(&'dw CStr),
     Unsigned(u64),
     Signed(i64),
+    /// A 128-bit constant, from `DW_FORM_data16`.
+    Unsigned128(u128),
     Address(u64),
     Die(Die<'dw>),
     Bytes(&'dw [u8]),
+    /// A DWARF expression, from `DW_FORM_exprloc` or a location block.
+    Expression(&'dw [u8]),
     Bool(bool),
+    /// An offset into `.debug_line`.
+    LinePtr(u64),
+    /// An offset into `.debug_loc` or `.debug_loclists`.
+    LocationList(u64),
+    /// An offset into `.debug_ranges` or `.debug_rnglists`.
+    RangeList(u64),
+    /// An offset into `.debug_macinfo` or `.debug_macro`.
+    MacroPtr(u64),
+    /// The base offset of the unit's `.debug_str_offsets` table.
+    StrOffsetsPtr(u64),
+    /// The base offset of the unit's `.debug_addr` table.
+    AddrPtr(u64),
+    /// The base offset of the unit's `.debug_rnglists` table.
+    RangeListsPtr(u64),
+    /// The base offset of the unit's `.debug_loclists` table.
+    LocationListsPtr(u64),
+    /// An index into the unit's `.debug_rnglists` offsets, from `DW_FORM_rnglistx`.
+    RangeListIndex(u64),
+    /// An index into the unit's `.debug_loclists` offsets, from `DW_FORM_loclistx`.
+    LocationListIndex(u64),
     #[doc(hidden)] // non-exhaustive
-    UnknownForm(u32),
+    UnknownForm(DwForm),
+}
+
+/// The class of an attribute value, as described in DWARF 5 section 7.5.5.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeClass {
+    Address,
+    AddrPtr,
+    Block,
+    Constant,
+    ExprLoc,
+    Flag,
+    LinePtr,
+    LocList,
+    LocListsPtr,
+    MacPtr,
+    Reference,
+    RngList,
+    RngListsPtr,
+    String,
+    StrOffsetsPtr,
 }
 
 impl<'dw> Default for Attribute<'dw> {
@@ -48,9 +95,14 @@ impl<'dw> Default for Attribute<'dw> {
 
 impl<'dw> fmt::Debug for Attribute<'dw> {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt.debug_tuple("Attribute")
-            .field(unsafe { &*self.as_ptr() })
-            .finish()
+        let mut debug = fmt.debug_struct("Attribute");
+        debug
+            .field("name", &self.name())
+            .field("form", &self.form());
+        if let Ok(value) = self.get_value() {
+            debug.field("value", &value);
+        }
+        debug.finish()
     }
 }
 
@@ -61,18 +113,18 @@ impl<'dw> Attribute<'dw> {
     }
 
     #[inline]
-    pub fn name(&self) -> u32 {
-        raw_ffi!(dwarf_whatattr(self.as_ptr()))
+    pub fn name(&self) -> DwAt {
+        DwAt(raw_ffi!(dwarf_whatattr(self.as_ptr())) as u16)
     }
 
     #[inline]
-    pub fn form(&self) -> u32 {
-        raw_ffi!(dwarf_whatform(self.as_ptr()))
+    pub fn form(&self) -> DwForm {
+        DwForm(raw_ffi!(dwarf_whatform(self.as_ptr())) as u16)
     }
 
     #[inline]
-    pub fn has_form(&self, form: u32) -> bool {
-        raw_ffi!(dwarf_hasform(self.as_ptr(), form)) != 0
+    pub fn has_form(&self, form: DwForm) -> bool {
+        raw_ffi!(dwarf_hasform(self.as_ptr(), form.into())) != 0
     }
 
     #[inline]
@@ -126,47 +178,223 @@ impl<'dw> Attribute<'dw> {
         Ok(flag)
     }
 
+    /// Get a 128-bit constant, as from `DW_FORM_data16`.
+    pub fn get_u128(&self) -> Result<u128> {
+        let bytes: [u8; 16] = match self.get_bytes()?.try_into() {
+            Ok(bytes) => bytes,
+            Err(_) => return Err(invalid("not a 16-byte constant")),
+        };
+        if big_endian(unsafe { (*self.as_ptr()).cu }) {
+            Ok(u128::from_be_bytes(bytes))
+        } else {
+            Ok(u128::from_le_bytes(bytes))
+        }
+    }
+
+    /// Get the DWARF version of the unit containing this attribute.
+    fn version(&self) -> Result<u16> {
+        let mut version = 0;
+        ffi!(dwarf_cu_info(
+            (*self.as_ptr()).cu,
+            &mut version,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut()
+        ))?;
+        Ok(version)
+    }
+
+    /// Resolve `DW_FORM_indirect`, where the actual form is encoded as a
+    /// ULEB128 just before the value.
+    fn resolve_indirect(&self) -> Result<Attribute<'dw>> {
+        let attr = self.clone();
+        let raw = unsafe { &mut *attr.as_ptr() };
+        if raw.valp.is_null() {
+            return Err(invalid("missing indirect form"));
+        }
+        let mut form = 0;
+        let mut shift = 0;
+        loop {
+            let byte = unsafe { *raw.valp };
+            raw.valp = unsafe { raw.valp.add(1) };
+            form |= u32::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+            if shift >= 32 {
+                return Err(invalid("indirect form is too large"));
+            }
+        }
+        if form == ffi::DW_FORM_indirect {
+            return Err(invalid("indirect form refers to itself"));
+        }
+        raw.form = form;
+        Ok(attr)
+    }
+
+    /// Classify this attribute's value, based on its name, form, and the
+    /// unit's DWARF version.
+    ///
+    /// Returns `None` for unrecognized forms.
+    pub fn class(&self) -> Result<Option<AttributeClass>> {
+        use self::AttributeClass as C;
+        let class = match self.form() {
+            DwForm::INDIRECT => return self.resolve_indirect()?.class(),
+
+            DwForm::ADDR
+            | DwForm::ADDRX
+            | DwForm::ADDRX1
+            | DwForm::ADDRX2
+            | DwForm::ADDRX3
+            | DwForm::ADDRX4
+            | DwForm::GNU_ADDR_INDEX => C::Address,
+
+            DwForm::STRP
+            | DwForm::STRING
+            | DwForm::LINE_STRP
+            | DwForm::STRX
+            | DwForm::STRX1
+            | DwForm::STRX2
+            | DwForm::STRX3
+            | DwForm::STRX4
+            | DwForm::STRP_SUP
+            | DwForm::GNU_STR_INDEX
+            | DwForm::GNU_STRP_ALT => C::String,
+
+            DwForm::REF_ADDR
+            | DwForm::REF_UDATA
+            | DwForm::REF8
+            | DwForm::REF4
+            | DwForm::REF2
+            | DwForm::REF1
+            | DwForm::REF_SIG8
+            | DwForm::REF_SUP4
+            | DwForm::REF_SUP8
+            | DwForm::GNU_REF_ALT => C::Reference,
+
+            DwForm::SEC_OFFSET => self.offset_class().unwrap_or(C::Constant),
+
+            // Before DWARF 4, section offsets were encoded as plain data.
+            DwForm::DATA8 | DwForm::DATA4 => match self.offset_class() {
+                Some(class) if self.version()? <= 3 => class,
+                _ => C::Constant,
+            },
+
+            DwForm::UDATA
+            | DwForm::DATA2
+            | DwForm::DATA1
+            | DwForm::DATA16
+            | DwForm::SDATA
+            | DwForm::IMPLICIT_CONST => C::Constant,
+
+            DwForm::RNGLISTX => C::RngList,
+            DwForm::LOCLISTX => C::LocList,
+
+            DwForm::FLAG_PRESENT | DwForm::FLAG => C::Flag,
+
+            DwForm::EXPRLOC => C::ExprLoc,
+
+            // Before DWARF 4, expressions were encoded as plain blocks.
+            DwForm::BLOCK4 | DwForm::BLOCK2 | DwForm::BLOCK1 | DwForm::BLOCK => {
+                match self.offset_class() {
+                    Some(C::LocList) if self.version()? <= 3 => C::ExprLoc,
+                    _ => C::Block,
+                }
+            }
+
+            _ => return Ok(None),
+        };
+        Ok(Some(class))
+    }
+
+    /// Get the class of a section offset for this attribute name, if any.
+    fn offset_class(&self) -> Option<AttributeClass> {
+        use self::AttributeClass as C;
+        let class = match self.name() {
+            DwAt::STMT_LIST => C::LinePtr,
+
+            DwAt::LOCATION
+            | DwAt::STRING_LENGTH
+            | DwAt::RETURN_ADDR
+            | DwAt::DATA_MEMBER_LOCATION
+            | DwAt::FRAME_BASE
+            | DwAt::SEGMENT
+            | DwAt::STATIC_LINK
+            | DwAt::USE_LOCATION
+            | DwAt::VTABLE_ELEM_LOCATION => C::LocList,
+
+            DwAt::RANGES | DwAt::START_SCOPE => C::RngList,
+
+            DwAt::MACRO_INFO | DwAt::MACROS | DwAt::GNU_MACROS => C::MacPtr,
+
+            DwAt::STR_OFFSETS_BASE => C::StrOffsetsPtr,
+            DwAt::ADDR_BASE | DwAt::GNU_ADDR_BASE => C::AddrPtr,
+            DwAt::RNGLISTS_BASE | DwAt::GNU_RANGES_BASE => C::RngListsPtr,
+            DwAt::LOCLISTS_BASE => C::LocListsPtr,
+
+            _ => return None,
+        };
+        Some(class)
+    }
+
+    /// Get the value, decoded according to its `class`.
     pub fn get_value(&self) -> Result<AttributeValue<'dw>> {
+        use self::AttributeClass as C;
         use self::AttributeValue as V;
-        let value = match self.form() {
-            ffi::DW_FORM_addr => V::Address(self.get_address()?),
-
-            ffi::DW_FORM_indirect
-            | ffi::DW_FORM_strp
-            | ffi::DW_FORM_string
-            | ffi::DW_FORM_GNU_strp_alt => V::String(self.get_string()?),
-
-            ffi::DW_FORM_ref_addr
-            | ffi::DW_FORM_ref_udata
-            | ffi::DW_FORM_ref8
-            | ffi::DW_FORM_ref4
-            | ffi::DW_FORM_ref2
-            | ffi::DW_FORM_ref1
-            | ffi::DW_FORM_ref_sig8
-            | ffi::DW_FORM_GNU_ref_alt => V::Die(self.get_die()?),
-
-            ffi::DW_FORM_sec_offset
-            | ffi::DW_FORM_udata
-            | ffi::DW_FORM_data8
-            | ffi::DW_FORM_data4
-            | ffi::DW_FORM_data2
-            | ffi::DW_FORM_data1 => V::Unsigned(self.get_unsigned()?),
-
-            ffi::DW_FORM_sdata => V::Signed(self.get_signed()?),
-
-            ffi::DW_FORM_flag_present | ffi::DW_FORM_flag => V::Bool(self.get_bool()?),
-
-            ffi::DW_FORM_exprloc
-            | ffi::DW_FORM_block4
-            | ffi::DW_FORM_block2
-            | ffi::DW_FORM_block1
-            | ffi::DW_FORM_block => V::Bytes(self.get_bytes()?),
-
-            form => V::UnknownForm(form),
+        if self.form() == DwForm::INDIRECT {
+            return self.resolve_indirect()?.get_value();
+        }
+        let class = match self.class()? {
+            Some(class) => class,
+            None => return Ok(V::UnknownForm(self.form())),
+        };
+        let value = match class {
+            C::Address => V::Address(self.get_address()?),
+            C::String => V::String(self.get_string()?),
+            C::Reference => V::Die(self.get_die()?),
+            C::Flag => V::Bool(self.get_bool()?),
+            C::Block => V::Bytes(self.get_bytes()?),
+            C::ExprLoc => V::Expression(self.get_bytes()?),
+
+            C::Constant => match self.form() {
+                DwForm::DATA16 => V::Unsigned128(self.get_u128()?),
+                DwForm::SDATA | DwForm::IMPLICIT_CONST => V::Signed(self.get_signed()?),
+                _ => V::Unsigned(self.get_unsigned()?),
+            },
+
+            C::RngList if self.form() == DwForm::RNGLISTX => {
+                V::RangeListIndex(self.get_unsigned()?)
+            }
+            C::LocList if self.form() == DwForm::LOCLISTX => {
+                V::LocationListIndex(self.get_unsigned()?)
+            }
+
+            C::LinePtr => V::LinePtr(self.get_unsigned()?),
+            C::LocList => V::LocationList(self.get_unsigned()?),
+            C::RngList => V::RangeList(self.get_unsigned()?),
+            C::MacPtr => V::MacroPtr(self.get_unsigned()?),
+            C::StrOffsetsPtr => V::StrOffsetsPtr(self.get_unsigned()?),
+            C::AddrPtr => V::AddrPtr(self.get_unsigned()?),
+            C::RngListsPtr => V::RangeListsPtr(self.get_unsigned()?),
+            C::LocListsPtr => V::LocationListsPtr(self.get_unsigned()?),
         };
         Ok(value)
     }
 
+    /// Iterate over the raw entries of the range list named by this attribute.
+    pub fn raw_range_list(&self) -> Result<RawRangeList<'dw>> {
+        RawRangeList::new(self)
+    }
+
+    /// Iterate over the raw entries of the location list named by this attribute.
+    pub fn raw_location_list(&self) -> Result<RawLocationList<'dw>> {
+        RawLocationList::new(self)
+    }
+
     #[inline]
     pub fn as_ptr(&self) -> *mut ffi::Dwarf_Attribute {
         self.inner.get()
@@ -185,6 +413,21 @@ impl<'dw> Clone for Attribute<'dw> {
     }
 }
 
+fn invalid(msg: &'static str) -> super::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg).into()
+}
+
+/// Check whether the file containing a unit is big-endian.
+pub(crate) fn big_endian(cu: *mut ffi::Dwarf_CU) -> bool {
+    unsafe {
+        let dwarf = ffi::dwarf_cu_getdwarf(cu);
+        let elf = ffi::dwarf_getelf(dwarf);
+        let ident = libelf::raw::elf_getident(elf, ptr::null_mut());
+        !ident.is_null()
+            && *ident.add(libelf::raw::EI_DATA as usize) as u32 == libelf::raw::ELFDATA2MSB
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -204,4 +447,41 @@ mod tests {
             align_of::<crate::ffi::Dwarf_Attribute>()
         );
     }
+
+    #[test]
+    fn known_forms() {
+        use crate::{AttributeValue, Die, Dwarf};
+
+        fn check(die: &Die<'_>) {
+            for attr in die.attrs().unwrap() {
+                let value = attr.get_value().unwrap();
+                assert!(
+                    !matches!(value, AttributeValue::UnknownForm(_)),
+                    "{:?}",
+                    attr
+                );
+            }
+            for child in die.iter_children() {
+                check(&child.unwrap());
+            }
+        }
+
+        let exe = std::env::current_exe().unwrap();
+        let dw = Dwarf::open(exe).unwrap();
+        for cu in dw.compile_units().take(10) {
+            check(&cu.unwrap().get_die().unwrap());
+        }
+    }
+
+    #[test]
+    fn typed_offsets() {
+        use crate::{AttributeClass, AttributeValue, DwAt, Dwarf};
+
+        let exe = std::env::current_exe().unwrap();
+        let dw = Dwarf::open(exe).unwrap();
+        let cu = dw.compile_units().next().unwrap().unwrap();
+        let attr = cu.get_die().unwrap().attr(DwAt::STMT_LIST).unwrap();
+        assert_eq!(attr.class().unwrap(), Some(AttributeClass::LinePtr));
+        assert!(matches!(attr.get_value(), Ok(AttributeValue::LinePtr(_))));
+    }
 }
@@ -0,0 +1,522 @@
+//! Typed DWARF constants, with `Display` showing their canonical names.
+
+use crate::ffi;
+
+use std::fmt;
+
+macro_rules! dw_constants {
+    (
+        $(#[$meta:meta])*
+        $name:ident($repr:ty), $prefix:expr;
+        named { $($named:ident = $named_raw:ident,)* }
+        aliases { $($alias:ident = $alias_raw:ident,)* }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(pub $repr);
+
+        impl $name {
+            $(pub const $named: $name = $name(ffi::$named_raw as $repr);)*
+            $(pub const $alias: $name = $name(ffi::$alias_raw as $repr);)*
+
+            /// Get the canonical name of this constant, if known.
+            pub fn static_name(self) -> Option<&'static str> {
+                match self {
+                    $($name::$named => Some(stringify!($named_raw)),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.static_name() {
+                    Some(name) => fmt.write_str(name),
+                    None => write!(fmt, "{}<{:#x}>", $prefix, self.0),
+                }
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(self, fmt)
+            }
+        }
+
+        impl From<$repr> for $name {
+            #[inline]
+            fn from(value: $repr) -> $name {
+                $name(value)
+            }
+        }
+
+        impl From<$name> for $repr {
+            #[inline]
+            fn from(value: $name) -> $repr {
+                value.0
+            }
+        }
+
+        // The raw bindings use `u32` for all of these.
+        impl From<$name> for u32 {
+            #[inline]
+            fn from(value: $name) -> u32 {
+                value.0.into()
+            }
+        }
+    };
+}
+
+dw_constants! {
+    /// A `DW_TAG_*` constant, identifying the kind of a DIE.
+    DwTag(u16), "DW_TAG_";
+    named {
+        INVALID = DW_TAG_invalid,
+        ARRAY_TYPE = DW_TAG_array_type,
+        CLASS_TYPE = DW_TAG_class_type,
+        ENTRY_POINT = DW_TAG_entry_point,
+        ENUMERATION_TYPE = DW_TAG_enumeration_type,
+        FORMAL_PARAMETER = DW_TAG_formal_parameter,
+        IMPORTED_DECLARATION = DW_TAG_imported_declaration,
+        LABEL = DW_TAG_label,
+        LEXICAL_BLOCK = DW_TAG_lexical_block,
+        MEMBER = DW_TAG_member,
+        POINTER_TYPE = DW_TAG_pointer_type,
+        REFERENCE_TYPE = DW_TAG_reference_type,
+        COMPILE_UNIT = DW_TAG_compile_unit,
+        STRING_TYPE = DW_TAG_string_type,
+        STRUCTURE_TYPE = DW_TAG_structure_type,
+        SUBROUTINE_TYPE = DW_TAG_subroutine_type,
+        TYPEDEF = DW_TAG_typedef,
+        UNION_TYPE = DW_TAG_union_type,
+        UNSPECIFIED_PARAMETERS = DW_TAG_unspecified_parameters,
+        VARIANT = DW_TAG_variant,
+        COMMON_BLOCK = DW_TAG_common_block,
+        COMMON_INCLUSION = DW_TAG_common_inclusion,
+        INHERITANCE = DW_TAG_inheritance,
+        INLINED_SUBROUTINE = DW_TAG_inlined_subroutine,
+        MODULE = DW_TAG_module,
+        PTR_TO_MEMBER_TYPE = DW_TAG_ptr_to_member_type,
+        SET_TYPE = DW_TAG_set_type,
+        SUBRANGE_TYPE = DW_TAG_subrange_type,
+        WITH_STMT = DW_TAG_with_stmt,
+        ACCESS_DECLARATION = DW_TAG_access_declaration,
+        BASE_TYPE = DW_TAG_base_type,
+        CATCH_BLOCK = DW_TAG_catch_block,
+        CONST_TYPE = DW_TAG_const_type,
+        CONSTANT = DW_TAG_constant,
+        ENUMERATOR = DW_TAG_enumerator,
+        FILE_TYPE = DW_TAG_file_type,
+        FRIEND = DW_TAG_friend,
+        NAMELIST = DW_TAG_namelist,
+        NAMELIST_ITEM = DW_TAG_namelist_item,
+        PACKED_TYPE = DW_TAG_packed_type,
+        SUBPROGRAM = DW_TAG_subprogram,
+        TEMPLATE_TYPE_PARAMETER = DW_TAG_template_type_parameter,
+        TEMPLATE_VALUE_PARAMETER = DW_TAG_template_value_parameter,
+        THROWN_TYPE = DW_TAG_thrown_type,
+        TRY_BLOCK = DW_TAG_try_block,
+        VARIANT_PART = DW_TAG_variant_part,
+        VARIABLE = DW_TAG_variable,
+        VOLATILE_TYPE = DW_TAG_volatile_type,
+        DWARF_PROCEDURE = DW_TAG_dwarf_procedure,
+        RESTRICT_TYPE = DW_TAG_restrict_type,
+        INTERFACE_TYPE = DW_TAG_interface_type,
+        NAMESPACE = DW_TAG_namespace,
+        IMPORTED_MODULE = DW_TAG_imported_module,
+        UNSPECIFIED_TYPE = DW_TAG_unspecified_type,
+        PARTIAL_UNIT = DW_TAG_partial_unit,
+        IMPORTED_UNIT = DW_TAG_imported_unit,
+        CONDITION = DW_TAG_condition,
+        SHARED_TYPE = DW_TAG_shared_type,
+        TYPE_UNIT = DW_TAG_type_unit,
+        RVALUE_REFERENCE_TYPE = DW_TAG_rvalue_reference_type,
+        TEMPLATE_ALIAS = DW_TAG_template_alias,
+        COARRAY_TYPE = DW_TAG_coarray_type,
+        GENERIC_SUBRANGE = DW_TAG_generic_subrange,
+        DYNAMIC_TYPE = DW_TAG_dynamic_type,
+        ATOMIC_TYPE = DW_TAG_atomic_type,
+        CALL_SITE = DW_TAG_call_site,
+        CALL_SITE_PARAMETER = DW_TAG_call_site_parameter,
+        SKELETON_UNIT = DW_TAG_skeleton_unit,
+        IMMUTABLE_TYPE = DW_TAG_immutable_type,
+        MIPS_LOOP = DW_TAG_MIPS_loop,
+        FORMAT_LABEL = DW_TAG_format_label,
+        FUNCTION_TEMPLATE = DW_TAG_function_template,
+        CLASS_TEMPLATE = DW_TAG_class_template,
+        GNU_BINCL = DW_TAG_GNU_BINCL,
+        GNU_EINCL = DW_TAG_GNU_EINCL,
+        GNU_TEMPLATE_TEMPLATE_PARAM = DW_TAG_GNU_template_template_param,
+        GNU_TEMPLATE_PARAMETER_PACK = DW_TAG_GNU_template_parameter_pack,
+        GNU_FORMAL_PARAMETER_PACK = DW_TAG_GNU_formal_parameter_pack,
+        GNU_CALL_SITE = DW_TAG_GNU_call_site,
+        GNU_CALL_SITE_PARAMETER = DW_TAG_GNU_call_site_parameter,
+    }
+    aliases {
+        LO_USER = DW_TAG_lo_user,
+        HI_USER = DW_TAG_hi_user,
+    }
+}
+
+dw_constants! {
+    /// A `DW_AT_*` constant, naming an attribute.
+    DwAt(u16), "DW_AT_";
+    named {
+        SUBSCR_DATA = DW_AT_subscr_data,
+        ELEMENT_LIST = DW_AT_element_list,
+        MEMBER = DW_AT_member,
+        SIBLING = DW_AT_sibling,
+        LOCATION = DW_AT_location,
+        NAME = DW_AT_name,
+        ORDERING = DW_AT_ordering,
+        BYTE_SIZE = DW_AT_byte_size,
+        BIT_OFFSET = DW_AT_bit_offset,
+        BIT_SIZE = DW_AT_bit_size,
+        STMT_LIST = DW_AT_stmt_list,
+        LOW_PC = DW_AT_low_pc,
+        HIGH_PC = DW_AT_high_pc,
+        LANGUAGE = DW_AT_language,
+        DISCR = DW_AT_discr,
+        DISCR_VALUE = DW_AT_discr_value,
+        VISIBILITY = DW_AT_visibility,
+        IMPORT = DW_AT_import,
+        STRING_LENGTH = DW_AT_string_length,
+        COMMON_REFERENCE = DW_AT_common_reference,
+        COMP_DIR = DW_AT_comp_dir,
+        CONST_VALUE = DW_AT_const_value,
+        CONTAINING_TYPE = DW_AT_containing_type,
+        DEFAULT_VALUE = DW_AT_default_value,
+        INLINE = DW_AT_inline,
+        IS_OPTIONAL = DW_AT_is_optional,
+        LOWER_BOUND = DW_AT_lower_bound,
+        PRODUCER = DW_AT_producer,
+        PROTOTYPED = DW_AT_prototyped,
+        RETURN_ADDR = DW_AT_return_addr,
+        START_SCOPE = DW_AT_start_scope,
+        BIT_STRIDE = DW_AT_bit_stride,
+        UPPER_BOUND = DW_AT_upper_bound,
+        ABSTRACT_ORIGIN = DW_AT_abstract_origin,
+        ACCESSIBILITY = DW_AT_accessibility,
+        ADDRESS_CLASS = DW_AT_address_class,
+        ARTIFICIAL = DW_AT_artificial,
+        BASE_TYPES = DW_AT_base_types,
+        CALLING_CONVENTION = DW_AT_calling_convention,
+        COUNT = DW_AT_count,
+        DATA_MEMBER_LOCATION = DW_AT_data_member_location,
+        DECL_COLUMN = DW_AT_decl_column,
+        DECL_FILE = DW_AT_decl_file,
+        DECL_LINE = DW_AT_decl_line,
+        DECLARATION = DW_AT_declaration,
+        DISCR_LIST = DW_AT_discr_list,
+        ENCODING = DW_AT_encoding,
+        EXTERNAL = DW_AT_external,
+        FRAME_BASE = DW_AT_frame_base,
+        FRIEND = DW_AT_friend,
+        IDENTIFIER_CASE = DW_AT_identifier_case,
+        MACRO_INFO = DW_AT_macro_info,
+        NAMELIST_ITEM = DW_AT_namelist_item,
+        PRIORITY = DW_AT_priority,
+        SEGMENT = DW_AT_segment,
+        SPECIFICATION = DW_AT_specification,
+        STATIC_LINK = DW_AT_static_link,
+        TYPE = DW_AT_type,
+        USE_LOCATION = DW_AT_use_location,
+        VARIABLE_PARAMETER = DW_AT_variable_parameter,
+        VIRTUALITY = DW_AT_virtuality,
+        VTABLE_ELEM_LOCATION = DW_AT_vtable_elem_location,
+        ALLOCATED = DW_AT_allocated,
+        ASSOCIATED = DW_AT_associated,
+        DATA_LOCATION = DW_AT_data_location,
+        BYTE_STRIDE = DW_AT_byte_stride,
+        ENTRY_PC = DW_AT_entry_pc,
+        USE_UTF8 = DW_AT_use_UTF8,
+        EXTENSION = DW_AT_extension,
+        RANGES = DW_AT_ranges,
+        TRAMPOLINE = DW_AT_trampoline,
+        CALL_COLUMN = DW_AT_call_column,
+        CALL_FILE = DW_AT_call_file,
+        CALL_LINE = DW_AT_call_line,
+        DESCRIPTION = DW_AT_description,
+        BINARY_SCALE = DW_AT_binary_scale,
+        DECIMAL_SCALE = DW_AT_decimal_scale,
+        SMALL = DW_AT_small,
+        DECIMAL_SIGN = DW_AT_decimal_sign,
+        DIGIT_COUNT = DW_AT_digit_count,
+        PICTURE_STRING = DW_AT_picture_string,
+        MUTABLE = DW_AT_mutable,
+        THREADS_SCALED = DW_AT_threads_scaled,
+        EXPLICIT = DW_AT_explicit,
+        OBJECT_POINTER = DW_AT_object_pointer,
+        ENDIANITY = DW_AT_endianity,
+        ELEMENTAL = DW_AT_elemental,
+        PURE = DW_AT_pure,
+        RECURSIVE = DW_AT_recursive,
+        SIGNATURE = DW_AT_signature,
+        MAIN_SUBPROGRAM = DW_AT_main_subprogram,
+        DATA_BIT_OFFSET = DW_AT_data_bit_offset,
+        CONST_EXPR = DW_AT_const_expr,
+        ENUM_CLASS = DW_AT_enum_class,
+        LINKAGE_NAME = DW_AT_linkage_name,
+        STRING_LENGTH_BIT_SIZE = DW_AT_string_length_bit_size,
+        STRING_LENGTH_BYTE_SIZE = DW_AT_string_length_byte_size,
+        RANK = DW_AT_rank,
+        STR_OFFSETS_BASE = DW_AT_str_offsets_base,
+        ADDR_BASE = DW_AT_addr_base,
+        RNGLISTS_BASE = DW_AT_rnglists_base,
+        DWO_NAME = DW_AT_dwo_name,
+        REFERENCE = DW_AT_reference,
+        RVALUE_REFERENCE = DW_AT_rvalue_reference,
+        MACROS = DW_AT_macros,
+        CALL_ALL_CALLS = DW_AT_call_all_calls,
+        CALL_ALL_SOURCE_CALLS = DW_AT_call_all_source_calls,
+        CALL_ALL_TAIL_CALLS = DW_AT_call_all_tail_calls,
+        CALL_RETURN_PC = DW_AT_call_return_pc,
+        CALL_VALUE = DW_AT_call_value,
+        CALL_ORIGIN = DW_AT_call_origin,
+        CALL_PARAMETER = DW_AT_call_parameter,
+        CALL_PC = DW_AT_call_pc,
+        CALL_TAIL_CALL = DW_AT_call_tail_call,
+        CALL_TARGET = DW_AT_call_target,
+        CALL_TARGET_CLOBBERED = DW_AT_call_target_clobbered,
+        CALL_DATA_LOCATION = DW_AT_call_data_location,
+        CALL_DATA_VALUE = DW_AT_call_data_value,
+        NORETURN = DW_AT_noreturn,
+        ALIGNMENT = DW_AT_alignment,
+        EXPORT_SYMBOLS = DW_AT_export_symbols,
+        DELETED = DW_AT_deleted,
+        DEFAULTED = DW_AT_defaulted,
+        LOCLISTS_BASE = DW_AT_loclists_base,
+        MIPS_FDE = DW_AT_MIPS_fde,
+        MIPS_LOOP_BEGIN = DW_AT_MIPS_loop_begin,
+        MIPS_TAIL_LOOP_BEGIN = DW_AT_MIPS_tail_loop_begin,
+        MIPS_EPILOG_BEGIN = DW_AT_MIPS_epilog_begin,
+        MIPS_LOOP_UNROLL_FACTOR = DW_AT_MIPS_loop_unroll_factor,
+        MIPS_SOFTWARE_PIPELINE_DEPTH = DW_AT_MIPS_software_pipeline_depth,
+        MIPS_LINKAGE_NAME = DW_AT_MIPS_linkage_name,
+        MIPS_STRIDE = DW_AT_MIPS_stride,
+        MIPS_ABSTRACT_NAME = DW_AT_MIPS_abstract_name,
+        MIPS_CLONE_ORIGIN = DW_AT_MIPS_clone_origin,
+        MIPS_HAS_INLINES = DW_AT_MIPS_has_inlines,
+        MIPS_STRIDE_BYTE = DW_AT_MIPS_stride_byte,
+        MIPS_STRIDE_ELEM = DW_AT_MIPS_stride_elem,
+        MIPS_PTR_DOPETYPE = DW_AT_MIPS_ptr_dopetype,
+        MIPS_ALLOCATABLE_DOPETYPE = DW_AT_MIPS_allocatable_dopetype,
+        MIPS_ASSUMED_SHAPE_DOPETYPE = DW_AT_MIPS_assumed_shape_dopetype,
+        MIPS_ASSUMED_SIZE = DW_AT_MIPS_assumed_size,
+        SF_NAMES = DW_AT_sf_names,
+        SRC_INFO = DW_AT_src_info,
+        MAC_INFO = DW_AT_mac_info,
+        SRC_COORDS = DW_AT_src_coords,
+        BODY_BEGIN = DW_AT_body_begin,
+        BODY_END = DW_AT_body_end,
+        GNU_VECTOR = DW_AT_GNU_vector,
+        GNU_GUARDED_BY = DW_AT_GNU_guarded_by,
+        GNU_PT_GUARDED_BY = DW_AT_GNU_pt_guarded_by,
+        GNU_GUARDED = DW_AT_GNU_guarded,
+        GNU_PT_GUARDED = DW_AT_GNU_pt_guarded,
+        GNU_LOCKS_EXCLUDED = DW_AT_GNU_locks_excluded,
+        GNU_EXCLUSIVE_LOCKS_REQUIRED = DW_AT_GNU_exclusive_locks_required,
+        GNU_SHARED_LOCKS_REQUIRED = DW_AT_GNU_shared_locks_required,
+        GNU_ODR_SIGNATURE = DW_AT_GNU_odr_signature,
+        GNU_TEMPLATE_NAME = DW_AT_GNU_template_name,
+        GNU_CALL_SITE_VALUE = DW_AT_GNU_call_site_value,
+        GNU_CALL_SITE_DATA_VALUE = DW_AT_GNU_call_site_data_value,
+        GNU_CALL_SITE_TARGET = DW_AT_GNU_call_site_target,
+        GNU_CALL_SITE_TARGET_CLOBBERED = DW_AT_GNU_call_site_target_clobbered,
+        GNU_TAIL_CALL = DW_AT_GNU_tail_call,
+        GNU_ALL_TAIL_CALL_SITES = DW_AT_GNU_all_tail_call_sites,
+        GNU_ALL_CALL_SITES = DW_AT_GNU_all_call_sites,
+        GNU_ALL_SOURCE_CALL_SITES = DW_AT_GNU_all_source_call_sites,
+        GNU_LOCVIEWS = DW_AT_GNU_locviews,
+        GNU_ENTRY_VIEW = DW_AT_GNU_entry_view,
+        GNU_MACROS = DW_AT_GNU_macros,
+        GNU_DELETED = DW_AT_GNU_deleted,
+        GNU_DWO_NAME = DW_AT_GNU_dwo_name,
+        GNU_DWO_ID = DW_AT_GNU_dwo_id,
+        GNU_RANGES_BASE = DW_AT_GNU_ranges_base,
+        GNU_ADDR_BASE = DW_AT_GNU_addr_base,
+        GNU_PUBNAMES = DW_AT_GNU_pubnames,
+        GNU_PUBTYPES = DW_AT_GNU_pubtypes,
+        GNU_NUMERATOR = DW_AT_GNU_numerator,
+        GNU_DENOMINATOR = DW_AT_GNU_denominator,
+        GNU_BIAS = DW_AT_GNU_bias,
+    }
+    aliases {
+        LO_USER = DW_AT_lo_user,
+        HI_USER = DW_AT_hi_user,
+    }
+}
+
+dw_constants! {
+    /// A `DW_FORM_*` constant, for the encoding of an attribute value.
+    DwForm(u16), "DW_FORM_";
+    named {
+        ADDR = DW_FORM_addr,
+        BLOCK2 = DW_FORM_block2,
+        BLOCK4 = DW_FORM_block4,
+        DATA2 = DW_FORM_data2,
+        DATA4 = DW_FORM_data4,
+        DATA8 = DW_FORM_data8,
+        STRING = DW_FORM_string,
+        BLOCK = DW_FORM_block,
+        BLOCK1 = DW_FORM_block1,
+        DATA1 = DW_FORM_data1,
+        FLAG = DW_FORM_flag,
+        SDATA = DW_FORM_sdata,
+        STRP = DW_FORM_strp,
+        UDATA = DW_FORM_udata,
+        REF_ADDR = DW_FORM_ref_addr,
+        REF1 = DW_FORM_ref1,
+        REF2 = DW_FORM_ref2,
+        REF4 = DW_FORM_ref4,
+        REF8 = DW_FORM_ref8,
+        REF_UDATA = DW_FORM_ref_udata,
+        INDIRECT = DW_FORM_indirect,
+        SEC_OFFSET = DW_FORM_sec_offset,
+        EXPRLOC = DW_FORM_exprloc,
+        FLAG_PRESENT = DW_FORM_flag_present,
+        STRX = DW_FORM_strx,
+        ADDRX = DW_FORM_addrx,
+        REF_SUP4 = DW_FORM_ref_sup4,
+        STRP_SUP = DW_FORM_strp_sup,
+        DATA16 = DW_FORM_data16,
+        LINE_STRP = DW_FORM_line_strp,
+        REF_SIG8 = DW_FORM_ref_sig8,
+        IMPLICIT_CONST = DW_FORM_implicit_const,
+        LOCLISTX = DW_FORM_loclistx,
+        RNGLISTX = DW_FORM_rnglistx,
+        REF_SUP8 = DW_FORM_ref_sup8,
+        STRX1 = DW_FORM_strx1,
+        STRX2 = DW_FORM_strx2,
+        STRX3 = DW_FORM_strx3,
+        STRX4 = DW_FORM_strx4,
+        ADDRX1 = DW_FORM_addrx1,
+        ADDRX2 = DW_FORM_addrx2,
+        ADDRX3 = DW_FORM_addrx3,
+        ADDRX4 = DW_FORM_addrx4,
+        GNU_ADDR_INDEX = DW_FORM_GNU_addr_index,
+        GNU_STR_INDEX = DW_FORM_GNU_str_index,
+        GNU_REF_ALT = DW_FORM_GNU_ref_alt,
+        GNU_STRP_ALT = DW_FORM_GNU_strp_alt,
+    }
+    aliases {
+    }
+}
+
+dw_constants! {
+    /// A `DW_LANG_*` constant, for the source language of a unit.
+    DwLang(u16), "DW_LANG_";
+    named {
+        C89 = DW_LANG_C89,
+        C = DW_LANG_C,
+        ADA83 = DW_LANG_Ada83,
+        C_PLUS_PLUS = DW_LANG_C_plus_plus,
+        COBOL74 = DW_LANG_Cobol74,
+        COBOL85 = DW_LANG_Cobol85,
+        FORTRAN77 = DW_LANG_Fortran77,
+        FORTRAN90 = DW_LANG_Fortran90,
+        PASCAL83 = DW_LANG_Pascal83,
+        MODULA2 = DW_LANG_Modula2,
+        JAVA = DW_LANG_Java,
+        C99 = DW_LANG_C99,
+        ADA95 = DW_LANG_Ada95,
+        FORTRAN95 = DW_LANG_Fortran95,
+        PLI = DW_LANG_PLI,
+        OBJC = DW_LANG_ObjC,
+        OBJC_PLUS_PLUS = DW_LANG_ObjC_plus_plus,
+        UPC = DW_LANG_UPC,
+        D = DW_LANG_D,
+        PYTHON = DW_LANG_Python,
+        OPENCL = DW_LANG_OpenCL,
+        GO = DW_LANG_Go,
+        MODULA3 = DW_LANG_Modula3,
+        HASKELL = DW_LANG_Haskell,
+        C_PLUS_PLUS_03 = DW_LANG_C_plus_plus_03,
+        C_PLUS_PLUS_11 = DW_LANG_C_plus_plus_11,
+        OCAML = DW_LANG_OCaml,
+        RUST = DW_LANG_Rust,
+        C11 = DW_LANG_C11,
+        SWIFT = DW_LANG_Swift,
+        JULIA = DW_LANG_Julia,
+        DYLAN = DW_LANG_Dylan,
+        C_PLUS_PLUS_14 = DW_LANG_C_plus_plus_14,
+        FORTRAN03 = DW_LANG_Fortran03,
+        FORTRAN08 = DW_LANG_Fortran08,
+        RENDERSCRIPT = DW_LANG_RenderScript,
+        BLISS = DW_LANG_BLISS,
+        MIPS_ASSEMBLER = DW_LANG_Mips_Assembler,
+    }
+    aliases {
+        LO_USER = DW_LANG_lo_user,
+        HI_USER = DW_LANG_hi_user,
+    }
+}
+
+dw_constants! {
+    /// A `DW_ATE_*` constant, for the encoding of a base type.
+    DwAte(u8), "DW_ATE_";
+    named {
+        VOID = DW_ATE_void,
+        ADDRESS = DW_ATE_address,
+        BOOLEAN = DW_ATE_boolean,
+        COMPLEX_FLOAT = DW_ATE_complex_float,
+        FLOAT = DW_ATE_float,
+        SIGNED = DW_ATE_signed,
+        SIGNED_CHAR = DW_ATE_signed_char,
+        UNSIGNED = DW_ATE_unsigned,
+        UNSIGNED_CHAR = DW_ATE_unsigned_char,
+        IMAGINARY_FLOAT = DW_ATE_imaginary_float,
+        PACKED_DECIMAL = DW_ATE_packed_decimal,
+        NUMERIC_STRING = DW_ATE_numeric_string,
+        EDITED = DW_ATE_edited,
+        SIGNED_FIXED = DW_ATE_signed_fixed,
+        UNSIGNED_FIXED = DW_ATE_unsigned_fixed,
+        DECIMAL_FLOAT = DW_ATE_decimal_float,
+        UTF = DW_ATE_UTF,
+        UCS = DW_ATE_UCS,
+        ASCII = DW_ATE_ASCII,
+    }
+    aliases {
+        LO_USER = DW_ATE_lo_user,
+        HI_USER = DW_ATE_hi_user,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DwAt, DwForm, DwLang, DwTag};
+
+    #[test]
+    fn names() {
+        assert_eq!(DwTag::SUBPROGRAM.to_string(), "DW_TAG_subprogram");
+        assert_eq!(DwTag::GNU_CALL_SITE.to_string(), "DW_TAG_GNU_call_site");
+        assert_eq!(DwAt::NAME.to_string(), "DW_AT_name");
+        assert_eq!(DwForm::STRX1.to_string(), "DW_FORM_strx1");
+        assert_eq!(DwLang::RUST.to_string(), "DW_LANG_Rust");
+        assert_eq!(DwTag(0x5555).to_string(), "DW_TAG_<0x5555>");
+        assert_eq!(DwTag::LO_USER.static_name(), None);
+    }
+
+    #[test]
+    fn readable_debug() {
+        let exe = std::env::current_exe().unwrap();
+        let dw = crate::Dwarf::open(exe).unwrap();
+        let die = dw
+            .compile_units()
+            .next()
+            .unwrap()
+            .unwrap()
+            .get_die()
+            .unwrap();
+        let debug = format!("{:?}", die);
+        assert!(debug.contains("tag: DW_TAG_compile_unit"), "{}", debug);
+
+        let attr = die.attr(DwAt::NAME).unwrap();
+        let debug = format!("{:?}", attr);
+        assert!(
+            debug.starts_with("Attribute { name: DW_AT_name, form: DW_FORM_"),
+            "{}",
+            debug
+        );
+    }
+}
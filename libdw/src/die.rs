@@ -8,10 +8,17 @@ use std::marker::PhantomData;
 use std::ops::Range;
 use std::panic;
 use std::ptr;
+use std::slice;
 
+use super::types;
 use super::Attribute;
+use super::Dimension;
 use super::Dwarf;
+use super::ParentIndex;
 use super::Result;
+use super::Scopes;
+use super::{Abbrev, Abbrevs};
+use super::{DwAt, DwAte, DwLang, DwTag};
 
 #[repr(transparent)]
 pub struct Die<'dw> {
@@ -37,9 +44,18 @@ impl<'dw> Default for Die<'dw> {
 
 impl<'dw> fmt::Debug for Die<'dw> {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt.debug_tuple("Die")
-            .field(unsafe { &*self.as_ptr() })
-            .finish()
+        if unsafe { (*self.as_ptr()).addr.is_null() } {
+            return fmt.write_str("Die(null)");
+        }
+        let mut debug = fmt.debug_struct("Die");
+        debug.field("offset", &format_args!("{:#x}", self.offset()));
+        if let Ok(tag) = self.tag() {
+            debug.field("tag", &tag);
+        }
+        if let Ok(name) = self.name() {
+            debug.field("name", &name);
+        }
+        debug.finish()
     }
 }
 
@@ -83,10 +99,10 @@ impl<'dw> Die<'dw> {
     }
 
     #[inline]
-    pub fn tag(&self) -> Result<u32> {
+    pub fn tag(&self) -> Result<DwTag> {
         let invalid = ffi::DW_TAG_invalid as libc::c_int;
         let tag = ffi_check!(dwarf_tag(self.as_ptr()) != invalid)?;
-        Ok(tag as u32)
+        Ok(DwTag(tag as u16))
     }
 
     #[inline]
@@ -115,6 +131,39 @@ impl<'dw> Die<'dw> {
         Ok(column as u32)
     }
 
+    /// Get the source file of an inlined subroutine's call site.
+    #[inline]
+    pub fn call_file(&self) -> Result<&'dw CStr> {
+        let index = self.attr(DwAt::CALL_FILE)?.get_unsigned()?;
+        let mut files = ptr::null_mut();
+        ffi!(dwarf_getsrcfiles(
+            self.unit()?.as_ptr(),
+            &mut files,
+            ptr::null_mut()
+        ))?;
+        let s = ffi!(dwarf_filesrc(
+            files,
+            index as libc::size_t,
+            ptr::null_mut(),
+            ptr::null_mut()
+        ))?;
+        Ok(unsafe { CStr::from_ptr(s) })
+    }
+
+    /// Get the source line of an inlined subroutine's call site.
+    #[inline]
+    pub fn call_line(&self) -> Result<u32> {
+        let line = self.attr(DwAt::CALL_LINE)?.get_unsigned()?;
+        Ok(line as u32)
+    }
+
+    /// Get the source column of an inlined subroutine's call site.
+    #[inline]
+    pub fn call_column(&self) -> Result<u32> {
+        let column = self.attr(DwAt::CALL_COLUMN)?.get_unsigned()?;
+        Ok(column as u32)
+    }
+
     #[inline]
     pub fn high_pc(&self) -> Result<u64> {
         let mut pc = 0;
@@ -136,6 +185,27 @@ impl<'dw> Die<'dw> {
         Ok(pc)
     }
 
+    /// Get the addresses to place breakpoints on entry to this function.
+    ///
+    /// These are found after the prologue, using the line table's
+    /// `prologue_end` markers where available.  If libdw finds nothing, this
+    /// falls back to the `entry_pc`.
+    pub fn breakpoint_addresses(&self) -> Result<Vec<u64>> {
+        let mut bkpts = ptr::null_mut();
+        let len = ffi!(dwarf_entry_breakpoints(self.as_ptr(), &mut bkpts))?;
+        let mut addrs = Vec::with_capacity(len as usize);
+        if !bkpts.is_null() {
+            unsafe {
+                addrs.extend_from_slice(slice::from_raw_parts(bkpts, len as usize));
+                libc::free(bkpts as *mut libc::c_void);
+            }
+        }
+        if addrs.is_empty() {
+            addrs.push(self.entry_pc()?);
+        }
+        Ok(addrs)
+    }
+
     #[inline]
     pub fn has_pc(&self, pc: u64) -> Result<bool> {
         let has = ffi!(dwarf_haspc(self.as_ptr(), pc))?;
@@ -175,10 +245,50 @@ impl<'dw> Die<'dw> {
         Ok(order as u32)
     }
 
+    /// Get the `DW_AT_encoding` of a base type.
     #[inline]
-    pub fn source_language(&self) -> Result<u32> {
+    pub fn encoding(&self) -> Result<DwAte> {
+        let encoding = self.attr(DwAt::ENCODING)?.get_unsigned()?;
+        Ok(DwAte(encoding as u8))
+    }
+
+    /// Strip typedefs and qualifiers (`const`, `volatile`, etc.) from a type.
+    #[inline]
+    pub fn peel_type(&self) -> Result<Die<'dw>> {
+        let die = Die::default();
+        ffi!(dwarf_peel_type(self.as_ptr(), die.as_ptr()))?;
+        Ok(die)
+    }
+
+    /// Compute the size of a type in bytes, including whole arrays.
+    #[inline]
+    pub fn aggregate_size(&self) -> Result<u64> {
+        let mut size = 0;
+        ffi!(dwarf_aggregate_size(self.as_ptr(), &mut size))?;
+        Ok(size)
+    }
+
+    /// Get the dimensions of an array type, outermost first.
+    ///
+    /// Missing lower bounds use the default for the unit's language.
+    #[inline]
+    pub fn array_dimensions(&self) -> Result<Vec<Dimension>> {
+        types::array_dimensions(self)
+    }
+
+    /// Render the name of a type, as it would be written in its language.
+    ///
+    /// C and C++ types are rendered as abstract declarators, like `char *` or
+    /// `int (*)(int, char **)`, while Rust types use Rust syntax.
+    #[inline]
+    pub fn type_name(&self) -> Result<String> {
+        types::type_name(self)
+    }
+
+    #[inline]
+    pub fn source_language(&self) -> Result<DwLang> {
         let lang = ffi!(dwarf_srclang(self.as_ptr()))?;
-        Ok(lang as u32)
+        Ok(DwLang(lang as u16))
     }
 
     #[inline]
@@ -193,6 +303,30 @@ impl<'dw> Die<'dw> {
         Ok(die)
     }
 
+    /// Get the unit DIE of the split unit, if this DIE's unit is a skeleton
+    /// from `-gsplit-dwarf` and its `.dwo` file can be found.
+    pub fn split_die(&self) -> Result<Option<Die<'dw>>> {
+        let mut unit_type = 0;
+        let subdie = Die::default();
+        ffi!(dwarf_cu_info(
+            (*self.as_ptr()).cu,
+            ptr::null_mut(),
+            &mut unit_type,
+            ptr::null_mut(),
+            subdie.as_ptr(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut()
+        ))?;
+        if u32::from(unit_type) == ffi::DW_UT_skeleton
+            && unsafe { !(*subdie.as_ptr()).addr.is_null() }
+        {
+            Ok(Some(subdie))
+        } else {
+            Ok(None)
+        }
+    }
+
     #[inline]
     pub fn offset(&self) -> ffi::Dwarf_Off {
         raw_ffi!(dwarf_dieoffset(self.as_ptr()))
@@ -225,6 +359,20 @@ impl<'dw> Die<'dw> {
         }
     }
 
+    /// Get the scopes containing `pc`, innermost first.
+    ///
+    /// This must be called on a compile unit DIE.
+    #[inline]
+    pub fn scopes_at(&self, pc: u64) -> Result<Scopes<'dw>> {
+        Scopes::from_pc(self, pc)
+    }
+
+    /// Get the scopes containing this DIE, starting with itself.
+    #[inline]
+    pub fn scopes(&self) -> Result<Scopes<'dw>> {
+        Scopes::from_die(self)
+    }
+
     #[inline]
     pub fn has_children(&self) -> Result<bool> {
         let rc = ffi!(dwarf_haschildren(self.as_ptr()))?;
@@ -240,6 +388,61 @@ impl<'dw> Die<'dw> {
         }
     }
 
+    /// Get the parent of this DIE.
+    ///
+    /// This indexes the whole unit each time, so keep a `ParentIndex` for
+    /// repeated lookups.  This is the lexical parent; see `ancestors` to
+    /// follow declarations.
+    #[inline]
+    pub fn parent(&self) -> Result<Option<Die<'dw>>> {
+        ParentIndex::new().parent(self)
+    }
+
+    /// Get the enclosing scopes of this DIE, starting from the unit DIE.
+    ///
+    /// See [`ParentIndex::ancestors`], which this uses with a new index.
+    #[inline]
+    pub fn ancestors(&self) -> Result<Vec<Die<'dw>>> {
+        ParentIndex::new().ancestors(self)
+    }
+
+    /// Get the name of this DIE, prefixed by its enclosing namespaces and
+    /// types, like `ns::Class::method`.
+    ///
+    /// See [`ParentIndex::qualified_name`], which this uses with a new index.
+    #[inline]
+    pub fn qualified_name(&self) -> Result<String> {
+        ParentIndex::new().qualified_name(self)
+    }
+
+    /// Follow `DW_AT_specification` and `DW_AT_abstract_origin` to the
+    /// original declaration.
+    pub(crate) fn declaration(&self) -> Result<Die<'dw>> {
+        let mut die = self.clone();
+        // Bound the chain in case of malformed cycles.
+        for _ in 0..8 {
+            if die.has_attr(DwAt::SPECIFICATION)? {
+                die = die.attr(DwAt::SPECIFICATION)?.get_die()?;
+            } else if die.has_attr(DwAt::ABSTRACT_ORIGIN)? {
+                die = die.attr(DwAt::ABSTRACT_ORIGIN)?.get_die()?;
+            } else {
+                break;
+            }
+        }
+        Ok(die)
+    }
+
+    /// Walk this DIE and all of its descendants, depth first.
+    #[inline]
+    pub fn tree(&self) -> DieTree<'dw> {
+        DieTree {
+            stack: vec![self.clone()],
+            started: false,
+            finished: false,
+            skip: false,
+        }
+    }
+
     pub fn for_each_child<F>(&self, mut f: F) -> Result<()>
     where
         F: FnMut(&Die<'dw>) -> Result<bool>,
@@ -256,31 +459,47 @@ impl<'dw> Die<'dw> {
     }
 
     #[inline]
-    pub fn has_attr(&self, name: u32) -> Result<bool> {
-        let b = ffi!(dwarf_hasattr(self.as_ptr(), name))?;
+    pub fn has_attr(&self, name: DwAt) -> Result<bool> {
+        let b = ffi!(dwarf_hasattr(self.as_ptr(), name.into()))?;
         Ok(b != 0)
     }
 
     #[inline]
-    pub fn has_attr_integrate(&self, name: u32) -> Result<bool> {
-        let b = ffi!(dwarf_hasattr_integrate(self.as_ptr(), name))?;
+    pub fn has_attr_integrate(&self, name: DwAt) -> Result<bool> {
+        let b = ffi!(dwarf_hasattr_integrate(self.as_ptr(), name.into()))?;
         Ok(b != 0)
     }
 
     #[inline]
-    pub fn attr(&self, name: u32) -> Result<Attribute<'dw>> {
+    pub fn attr(&self, name: DwAt) -> Result<Attribute<'dw>> {
         let attr = Attribute::default();
-        ffi!(dwarf_attr(self.as_ptr(), name, attr.as_ptr()))?;
+        ffi!(dwarf_attr(self.as_ptr(), name.into(), attr.as_ptr()))?;
         Ok(attr)
     }
 
     #[inline]
-    pub fn attr_integrate(&self, name: u32) -> Result<Attribute<'dw>> {
+    pub fn attr_integrate(&self, name: DwAt) -> Result<Attribute<'dw>> {
         let attr = Attribute::default();
-        ffi!(dwarf_attr_integrate(self.as_ptr(), name, attr.as_ptr()))?;
+        ffi!(dwarf_attr_integrate(
+            self.as_ptr(),
+            name.into(),
+            attr.as_ptr()
+        ))?;
         Ok(attr)
     }
 
+    /// Get the abbreviation that describes this DIE's tag and attributes.
+    #[inline]
+    pub fn abbrev(&self) -> Result<Abbrev<'dw>> {
+        Ok(Abbrev::new(self.get_abbrev()?))
+    }
+
+    /// Iterate over the abbreviation table of this DIE's unit.
+    #[inline]
+    pub fn abbrevs(&self) -> Abbrevs<'dw> {
+        Abbrevs::new(self)
+    }
+
     #[inline]
     pub fn attr_count(&self) -> Result<usize> {
         let mut count = 0;
@@ -399,6 +618,67 @@ impl<'dw> Die<'dw> {
         }
     }
 
+    /// Check whether this is an abstract inline function definition.
+    #[inline]
+    pub fn func_inline(&self) -> Result<bool> {
+        let rc = ffi!(dwarf_func_inline(self.as_ptr()))?;
+        Ok(rc != 0)
+    }
+
+    /// Visit each concrete inlined instance of this abstract inline function.
+    pub fn for_each_inline_instance<F>(&self, mut f: F) -> Result<()>
+    where
+        F: FnMut(&Die<'dw>) -> Result<bool>,
+    {
+        let mut guard = CallbackGuard::new();
+        let mut result = Ok(());
+
+        unsafe {
+            self.getinstances(|inst| guard.call(|| dwarf_cb_map(f(inst), &mut result)))?;
+        }
+
+        result
+    }
+
+    /// Visit each concrete inlined instance, without catching panics.
+    ///
+    /// # Safety
+    ///
+    /// The callback must not panic, as unwinding through libdw is undefined.
+    pub unsafe fn for_each_inline_instance_unchecked<F>(&self, mut f: F) -> Result<()>
+    where
+        F: FnMut(&Die<'dw>) -> Result<bool>,
+    {
+        let mut result = Ok(());
+
+        self.getinstances(|inst| dwarf_cb_map(f(inst), &mut result))?;
+
+        result
+    }
+
+    unsafe fn getinstances<F>(&self, mut f: F) -> Result<libc::c_int>
+    where
+        F: FnMut(&Die<'dw>) -> libc::c_uint,
+    {
+        let argp = &mut f as *mut F as *mut libc::c_void;
+        return ffi!(dwarf_func_inline_instances(
+            self.as_ptr(),
+            Some(callback::<'dw, F>),
+            argp
+        ));
+
+        unsafe extern "C" fn callback<'a, F>(
+            inst: *mut ffi::Dwarf_Die,
+            argp: *mut libc::c_void,
+        ) -> libc::c_int
+        where
+            F: FnMut(&Die<'a>) -> libc::c_uint,
+        {
+            let f = &mut *(argp as *mut F);
+            f(Die::from_ptr(inst)) as libc::c_int
+        }
+    }
+
     #[inline]
     pub fn as_ptr(&self) -> *mut ffi::Dwarf_Die {
         self.inner.get()
@@ -459,6 +739,100 @@ impl<'dw> Iterator for DieChildren<'dw> {
     }
 }
 
+/// A depth-first walk over a DIE and its descendants, from `Die::tree`.
+///
+/// The tree's root is yielded first, at depth 0.  While iterating, the
+/// cursor methods describe the most recently yielded DIE.
+#[derive(Debug)]
+pub struct DieTree<'dw> {
+    // The path from the root down to the current DIE.
+    stack: Vec<Die<'dw>>,
+    started: bool,
+    finished: bool,
+    skip: bool,
+}
+
+impl<'dw> DieTree<'dw> {
+    /// Get the depth of the current DIE below the root.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.stack.len() - 1
+    }
+
+    /// Don't descend into the children of the current DIE.
+    #[inline]
+    pub fn skip_children(&mut self) {
+        self.skip = true;
+    }
+
+    /// Get the ancestors of the current DIE, starting from the root.
+    #[inline]
+    pub fn ancestors(&self) -> &[Die<'dw>] {
+        &self.stack[..self.depth()]
+    }
+
+    /// Get the parent of the current DIE, unless it's the root.
+    #[inline]
+    pub fn parent(&self) -> Option<&Die<'dw>> {
+        self.ancestors().last()
+    }
+
+    fn advance(&mut self) -> Result<bool> {
+        if !self.skip {
+            if let Some(child) = self.stack[self.depth()].child()? {
+                self.stack.push(child);
+                return Ok(true);
+            }
+        }
+        self.skip = false;
+
+        // Never leave the root, even if it has siblings.
+        while self.stack.len() > 1 {
+            let die = self.stack.pop().unwrap();
+            if let Some(sibling) = die.siblingof()? {
+                self.stack.push(sibling);
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl<'dw> Iterator for DieTree<'dw> {
+    type Item = Result<Die<'dw>>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let rc = if self.started {
+            self.advance()
+        } else {
+            self.started = true;
+            Ok(true)
+        };
+
+        match rc {
+            Ok(true) => {
+                let die = &self.stack[self.depth()];
+                // prime the die->abbrev before we Clone
+                die.get_abbrev().ok();
+                Some(Ok(die.clone()))
+            }
+            Ok(false) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct DieRanges<'dw> {
     offset: isize,
@@ -555,7 +929,7 @@ impl Drop for CallbackGuard {
 
 #[cfg(test)]
 mod tests {
-    use super::Dwarf;
+    use crate::test_util::current;
 
     #[test]
     fn die_size() {
@@ -575,12 +949,6 @@ mod tests {
         );
     }
 
-    fn current() -> Dwarf<'static> {
-        use std::env;
-        let exe = env::current_exe().unwrap();
-        Dwarf::open(exe).unwrap()
-    }
-
     #[test]
     fn attr_callback() {
         for cu in current().compile_units() {
@@ -599,6 +967,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn breakpoints() {
+        for cu in current().compile_units() {
+            let die = cu.unwrap().get_die().unwrap();
+            die.for_each_func(|func| {
+                if func.low_pc().is_ok_and(|pc| pc != 0) {
+                    for addr in func.breakpoint_addresses()? {
+                        assert!(func.has_pc(addr)?);
+                    }
+                }
+                Ok(true)
+            })
+            .unwrap();
+        }
+    }
+
     #[test]
     #[should_panic]
     fn attr_callback_panic() {
@@ -607,4 +991,117 @@ mod tests {
             die.for_each_attr(|_| panic!()).unwrap();
         }
     }
+
+    #[test]
+    fn tree_walk() {
+        use crate::{Die, Result};
+
+        // Record each DIE's offset and depth, recursively.
+        fn recurse(die: &Die<'_>, depth: usize, out: &mut Vec<(u64, usize)>) -> Result<()> {
+            out.push((die.offset(), depth));
+            for child in die.iter_children() {
+                recurse(&child?, depth + 1, out)?;
+            }
+            Ok(())
+        }
+
+        let dw = current();
+        let cu = dw.compile_units().next().unwrap().unwrap();
+        let die = cu.get_die().unwrap();
+
+        let mut expected = Vec::new();
+        recurse(&die, 0, &mut expected).unwrap();
+
+        let mut found = Vec::new();
+        let mut tree = die.tree();
+        while let Some(die) = tree.next() {
+            let die = die.unwrap();
+            assert_eq!(tree.ancestors().len(), tree.depth());
+            if let Some(parent) = tree.parent() {
+                assert!(parent.offset() < die.offset());
+            }
+            found.push((die.offset(), tree.depth()));
+        }
+        assert_eq!(found, expected);
+
+        // Skipping at the first level leaves only the root and its children.
+        let mut tree = cu.tree().unwrap();
+        let mut count = 0;
+        while let Some(die) = tree.next() {
+            die.unwrap();
+            if tree.depth() == 1 {
+                tree.skip_children();
+            }
+            count += 1;
+        }
+        assert_eq!(count, 1 + die.iter_children().count());
+    }
+
+    #[test]
+    fn parents() {
+        use crate::{Die, DwAt, ParentIndex};
+
+        let dw = current();
+        let index = ParentIndex::new();
+        let cu = dw.compile_units().next().unwrap().unwrap();
+        let mut tree = cu.tree().unwrap();
+        while let Some(die) = tree.next() {
+            let die = Die::from_offset(&dw, die.unwrap().offset()).unwrap();
+            let parent = index.parent(&die).unwrap();
+            assert_eq!(
+                parent.map(|p| p.offset()),
+                tree.parent().map(|p| p.offset())
+            );
+
+            // Without declarations to follow, ancestors match the tree path.
+            let lexical = tree.ancestors().iter().chain(Some(&die)).all(|d| {
+                !d.has_attr(DwAt::SPECIFICATION).unwrap()
+                    && !d.has_attr(DwAt::ABSTRACT_ORIGIN).unwrap()
+            });
+            if lexical {
+                let ancestors = index.ancestors(&die).unwrap();
+                let expected: Vec<_> = tree.ancestors().iter().map(|d| d.offset()).collect();
+                let found: Vec<_> = ancestors.iter().map(|d| d.offset()).collect();
+                assert_eq!(found, expected);
+                if tree.depth() == 1 {
+                    // The uncached methods agree for the unit's children.
+                    let found: Vec<_> = die
+                        .ancestors()
+                        .unwrap()
+                        .iter()
+                        .map(|d| d.offset())
+                        .collect();
+                    assert_eq!(found, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn qualified_name() {
+        use crate::{DwTag, ParentIndex};
+
+        let dw = current();
+        let index = ParentIndex::new();
+        let mut found = 0;
+        for cu in dw.compile_units() {
+            let mut tree = cu.unwrap().tree().unwrap();
+            while let Some(die) = tree.next() {
+                let die = die.unwrap();
+                if die.tag().unwrap() == DwTag::SUBPROGRAM
+                    && die.name().ok().map(|name| name.to_bytes()) == Some(b"qualified_name")
+                    && tree
+                        .ancestors()
+                        .iter()
+                        .any(|d| d.name().ok().map(|n| n.to_bytes()) == Some(b"tests"))
+                {
+                    let name = index.qualified_name(&die).unwrap();
+                    assert_eq!(name, "libdw::die::tests::qualified_name");
+                    assert_eq!(die.qualified_name().unwrap(), name);
+                    found += 1;
+                }
+            }
+        }
+        assert!(found > 0);
+    }
 }
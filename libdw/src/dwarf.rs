@@ -1,23 +1,28 @@
 use crate::ffi;
 use std::ptr;
 
+use std::ffi::{CStr, OsStr};
 use std::fmt;
 use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
 use std::os::unix::io::AsRawFd;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::slice;
 
 use super::Result;
-use super::{CompileUnits, TypeUnits};
+use super::{CompileUnits, Die, OffsetAbbrevs, Scopes, TypeUnits, Units};
 
 #[derive(Debug)]
 pub struct Dwarf<'dw> {
     inner: *mut ffi::Dwarf,
     kind: DwarfKind<'dw>,
+    alt: Option<Box<Dwarf<'dw>>>,
 }
 
 enum DwarfKind<'dw> {
     Raw,
-    File(fs::File),
+    File(fs::File, PathBuf),
     Fd(&'dw dyn AsRawFd),
     Elf(&'dw libelf::Elf<'dw>),
 }
@@ -26,7 +31,9 @@ impl<'elf> fmt::Debug for DwarfKind<'elf> {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             DwarfKind::Raw => fmt.debug_tuple("Raw").finish(),
-            DwarfKind::File(ref f) => fmt.debug_tuple("File").field(f).finish(),
+            DwarfKind::File(ref f, ref path) => {
+                fmt.debug_tuple("File").field(f).field(path).finish()
+            }
             DwarfKind::Fd(f) => fmt.debug_tuple("Fd").field(&f.as_raw_fd()).finish(),
             DwarfKind::Elf(e) => fmt.debug_tuple("Elf").field(&e).finish(),
         }
@@ -39,6 +46,7 @@ impl<'dw> Dwarf<'dw> {
         Dwarf {
             inner: dwarf,
             kind: kind,
+            alt: None,
         }
     }
 
@@ -51,10 +59,11 @@ impl<'dw> Dwarf<'dw> {
     /// let dw = libdw::Dwarf::open(exe).unwrap();
     /// ```
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Dwarf<'static>> {
+        let path = path.as_ref();
         let file = fs::File::open(path)?;
         let raw_fd = file.as_raw_fd();
         let dwarf = ffi!(dwarf_begin(raw_fd, ffi::Dwarf_Cmd::DWARF_C_READ))?;
-        Ok(Dwarf::new(dwarf, DwarfKind::File(file)))
+        Ok(Dwarf::new(dwarf, DwarfKind::File(file, path.to_owned())))
     }
 
     /// Create a `Dwarf` from an open file.
@@ -114,6 +123,108 @@ impl<'dw> Dwarf<'dw> {
         unsafe { libelf::Elf::from_raw(elf) }
     }
 
+    /// Set the alternate debug file, as created by `dwz`, which resolves
+    /// `DW_FORM_GNU_ref_alt` and `DW_FORM_GNU_strp_alt` attributes.
+    ///
+    /// This `Dwarf` takes ownership of the alternate, so it must own its
+    /// libdw handle too.  Borrowed wrappers, like those from
+    /// [`from_raw`](Dwarf::from_raw) and [`get_alt`](Dwarf::get_alt), return
+    /// an error, since the alternate would be freed while libdw still uses it.
+    #[inline]
+    pub fn set_alt(&mut self, alt: Dwarf<'dw>) -> Result<()> {
+        self.check_owned()?;
+        raw_ffi!(dwarf_setalt(self.as_ptr(), alt.as_ptr()));
+        self.alt = Some(Box::new(alt));
+        Ok(())
+    }
+
+    /// Get the alternate debug file, if any.
+    ///
+    /// If none was set, libdw makes its own search next to the main file and
+    /// in the `.build-id` debug directory.
+    #[inline]
+    pub fn get_alt(&self) -> Option<Dwarf<'_>> {
+        let alt = raw_ffi!(dwarf_getalt(self.as_ptr()));
+        if alt.is_null() {
+            None
+        } else {
+            Some(unsafe { Dwarf::from_raw(alt) })
+        }
+    }
+
+    /// Find and set the alternate debug file named by `.gnu_debugaltlink`.
+    ///
+    /// Candidates are the linked path, relative to the main file if opened
+    /// by path, then `/usr/lib/debug/.dwz`, then the `.build-id` debug
+    /// directory.  Only a file with the expected build-id is accepted.
+    ///
+    /// Returns `false` if there's no link or no matching file was found.
+    /// Like [`set_alt`](Dwarf::set_alt), this is an error for a borrowed
+    /// wrapper.
+    pub fn setup_alt(&mut self) -> Result<bool> {
+        self.check_owned()?;
+        let mut namep = ptr::null();
+        let mut build_idp = ptr::null();
+        let len = ffi!(dwelf_dwarf_gnu_debugaltlink(
+            self.as_ptr(),
+            &mut namep,
+            &mut build_idp
+        ))?;
+        if len == 0 {
+            return Ok(false);
+        }
+
+        let (name, build_id) = unsafe {
+            let name = CStr::from_ptr(namep).to_bytes();
+            let build_id = slice::from_raw_parts(build_idp as *const u8, len as usize);
+            (PathBuf::from(OsStr::from_bytes(name)), build_id.to_vec())
+        };
+
+        let dir = match self.kind {
+            DwarfKind::File(_, ref path) => path.parent(),
+            _ => None,
+        };
+        for path in alt_candidates(dir, &name, &build_id) {
+            if let Ok(alt) = Dwarf::open(&path) {
+                if alt.build_id() == Some(&build_id[..]) {
+                    self.set_alt(alt)?;
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Check that this wrapper owns its libdw handle.
+    fn check_owned(&self) -> Result<()> {
+        match self.kind {
+            DwarfKind::Raw => {
+                let msg = "the alternate of a borrowed Dwarf can't be set";
+                Err(io::Error::new(io::ErrorKind::InvalidInput, msg).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Get the GNU build-id of the underlying ELF file, if present.
+    fn build_id(&self) -> Option<&[u8]> {
+        let elf = raw_ffi!(dwarf_getelf(self.as_ptr()));
+        let mut build_idp = ptr::null();
+        let len = raw_ffi!(dwelf_elf_gnu_build_id(elf, &mut build_idp));
+        if len > 0 {
+            Some(unsafe { slice::from_raw_parts(build_idp as *const u8, len as usize) })
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over the abbreviation table at `offset` in `.debug_abbrev`,
+    /// as named by a unit header's `abbrev_offset`.
+    #[inline]
+    pub fn abbrevs_at(&'dw self, offset: u64) -> OffsetAbbrevs<'dw> {
+        OffsetAbbrevs::new(self, offset)
+    }
+
     #[inline]
     pub fn compile_units(&'dw self) -> CompileUnits<'dw> {
         CompileUnits::new(self)
@@ -124,12 +235,53 @@ impl<'dw> Dwarf<'dw> {
         TypeUnits::new(self)
     }
 
+    /// Iterate over every unit, including partial, type, and skeleton units.
+    #[inline]
+    pub fn units(&'dw self) -> Units<'dw> {
+        Units::new(self)
+    }
+
+    /// Get the scopes containing `pc`, innermost first.
+    #[inline]
+    pub fn scopes_at(&'dw self, pc: u64) -> Result<Scopes<'dw>> {
+        Die::from_address(self, pc)?.scopes_at(pc)
+    }
+
+    /// Get the chain of inlined functions containing `pc`, innermost first.
+    ///
+    /// See [`Scopes::inline_chain`].
+    #[inline]
+    pub fn inline_chain(&'dw self, pc: u64) -> Result<Vec<Die<'dw>>> {
+        self.scopes_at(pc)?.inline_chain()
+    }
+
     #[inline]
     pub fn as_ptr(&self) -> *mut ffi::Dwarf {
         self.inner
     }
 }
 
+const DEBUG_DIR: &str = "/usr/lib/debug";
+
+/// List the paths that may hold an alternate debug file.
+fn alt_candidates(dir: Option<&Path>, name: &Path, build_id: &[u8]) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if name.is_absolute() {
+        paths.push(name.to_owned());
+    } else if let Some(dir) = dir {
+        paths.push(dir.join(name));
+    }
+    if let Some(file_name) = name.file_name() {
+        paths.push(Path::new(DEBUG_DIR).join(".dwz").join(file_name));
+    }
+    if let Some((first, rest)) = build_id.split_first() {
+        let rest: String = rest.iter().map(|b| format!("{:02x}", b)).collect();
+        let path = format!("{}/.build-id/{:02x}/{}.debug", DEBUG_DIR, first, rest);
+        paths.push(path.into());
+    }
+    paths
+}
+
 impl<'dw> Drop for Dwarf<'dw> {
     #[inline]
     fn drop(&mut self) {
@@ -141,3 +293,50 @@ impl<'dw> Drop for Dwarf<'dw> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{alt_candidates, Dwarf};
+    use crate::test_util::current;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn no_alt() {
+        let mut dw = current();
+        assert!(!dw.setup_alt().unwrap());
+        assert!(dw.get_alt().is_none());
+    }
+
+    #[test]
+    fn set_alt() {
+        let mut dw = current();
+        let alt = current();
+        let ptr = alt.as_ptr();
+        dw.set_alt(alt).unwrap();
+        assert_eq!(dw.get_alt().unwrap().as_ptr(), ptr);
+    }
+
+    #[test]
+    fn borrowed_alt() {
+        let dw = current();
+        let mut raw = unsafe { Dwarf::from_raw(dw.as_ptr()) };
+        assert!(raw.set_alt(current()).is_err());
+        assert!(raw.setup_alt().is_err());
+        assert!(dw.get_alt().is_none());
+    }
+
+    #[test]
+    fn candidates() {
+        let paths = alt_candidates(
+            Some(Path::new("/usr/lib/debug/usr/bin")),
+            Path::new("../../.dwz/foo"),
+            &[0xab, 0xcd, 0xef],
+        );
+        let expected: Vec<PathBuf> = vec![
+            "/usr/lib/debug/usr/bin/../../.dwz/foo".into(),
+            "/usr/lib/debug/.dwz/foo".into(),
+            "/usr/lib/debug/.build-id/ab/cdef.debug".into(),
+        ];
+        assert_eq!(paths, expected);
+    }
+}
@@ -0,0 +1,410 @@
+use crate::ffi;
+
+use std::ffi::CStr;
+use std::io;
+use std::ptr;
+use std::slice;
+
+use super::attr::big_endian;
+use super::Attribute;
+use super::Die;
+use super::Result;
+use super::{DwAt, DwTag};
+
+/// The memory layout of a structure, class, or union type.
+///
+/// Offsets and sizes are tracked in bits, since bit-field members may start
+/// and end in the middle of a byte.
+#[derive(Debug)]
+pub struct Layout<'dw> {
+    die: Die<'dw>,
+    size: u64,
+    union: bool,
+    members: Vec<Member<'dw>>,
+    holes: Vec<Option<Hole>>,
+    padding: u64,
+}
+
+/// A data member or base class within a `Layout`.
+#[derive(Debug, Clone)]
+pub struct Member<'dw> {
+    die: Die<'dw>,
+    ty: Option<Die<'dw>>,
+    bit_offset: u64,
+    bit_size: u64,
+    bitfield: bool,
+}
+
+/// Unused space between members of a `Layout`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hole {
+    bit_offset: u64,
+    bit_size: u64,
+}
+
+impl<'dw> Layout<'dw> {
+    /// Compute the layout of a `DW_TAG_structure_type`, `DW_TAG_class_type`,
+    /// or `DW_TAG_union_type`.
+    ///
+    /// Static members are skipped, while base classes are included as members.
+    /// Virtual base classes are also skipped, since their offset is only known
+    /// at run time.
+    pub fn new(die: &Die<'dw>) -> Result<Layout<'dw>> {
+        let union = match die.tag()? {
+            DwTag::STRUCTURE_TYPE | DwTag::CLASS_TYPE => false,
+            DwTag::UNION_TYPE => true,
+            _ => return Err(invalid("not a structure, class, or union type")),
+        };
+        let size = die.aggregate_size()?;
+
+        let mut members = Vec::new();
+        for child in die.iter_children() {
+            let child = child?;
+            match child.tag()? {
+                DwTag::MEMBER | DwTag::INHERITANCE => {
+                    if child.has_attr(DwAt::DECLARATION)? || child.has_attr(DwAt::EXTERNAL)? {
+                        continue;
+                    }
+                    members.extend(Member::new(child)?);
+                }
+                _ => (),
+            }
+        }
+
+        // Some languages reorder fields, so walk them in memory order.
+        members.sort_by_key(|member: &Member<'_>| member.bit_offset);
+
+        // Unions overlap everything, so only structures have holes.
+        let mut holes = Vec::with_capacity(members.len());
+        let mut end = 0;
+        for member in &members {
+            if !union && member.bit_offset > end {
+                holes.push(Some(Hole {
+                    bit_offset: end,
+                    bit_size: member.bit_offset - end,
+                }));
+            } else {
+                holes.push(None);
+            }
+            let member_end = member.bit_offset.checked_add(member.bit_size);
+            end = end.max(member_end.ok_or_else(|| invalid("member size out of range"))?);
+        }
+        let padding = bits(size)?.saturating_sub(end);
+
+        Ok(Layout {
+            die: die.clone(),
+            size,
+            union,
+            members,
+            holes,
+            padding,
+        })
+    }
+
+    /// Get the type DIE of this layout.
+    #[inline]
+    pub fn die(&self) -> &Die<'dw> {
+        &self.die
+    }
+
+    /// Get the total size in bytes.
+    #[inline]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Check whether this is a union, where all members overlap.
+    #[inline]
+    pub fn is_union(&self) -> bool {
+        self.union
+    }
+
+    /// Get the members, in order of their offset.
+    #[inline]
+    pub fn members(&self) -> &[Member<'dw>] {
+        &self.members
+    }
+
+    /// Get the hole just before the member at `index`, if any.
+    #[inline]
+    pub fn hole_before(&self, index: usize) -> Option<Hole> {
+        self.holes.get(index).cloned().flatten()
+    }
+
+    /// Get all holes between members.
+    #[inline]
+    pub fn holes(&self) -> impl Iterator<Item = Hole> + '_ {
+        self.holes.iter().flatten().cloned()
+    }
+
+    /// Get the unused bits after the last member.
+    #[inline]
+    pub fn padding_bits(&self) -> u64 {
+        self.padding
+    }
+
+    /// Get the number of cachelines spanned, for a given cacheline size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cacheline_size` is zero.
+    #[inline]
+    pub fn cachelines(&self, cacheline_size: u64) -> u64 {
+        assert!(cacheline_size > 0, "cacheline size must be nonzero");
+        self.size.div_ceil(cacheline_size)
+    }
+
+    /// Get the index of each member that starts a new cacheline, with the
+    /// number of that cacheline.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cacheline_size` is zero.
+    pub fn cacheline_boundaries(&self, cacheline_size: u64) -> Vec<(usize, u64)> {
+        assert!(cacheline_size > 0, "cacheline size must be nonzero");
+        let mut boundaries = Vec::new();
+        let mut line = 0;
+        for (index, member) in self.members.iter().enumerate() {
+            let member_line = member.offset() / cacheline_size;
+            if member_line > line {
+                line = member_line;
+                boundaries.push((index, line));
+            }
+        }
+        boundaries
+    }
+}
+
+impl<'dw> Member<'dw> {
+    fn new(die: Die<'dw>) -> Result<Option<Member<'dw>>> {
+        let byte_offset = if die.has_attr(DwAt::DATA_MEMBER_LOCATION)? {
+            match member_location(&die.attr(DwAt::DATA_MEMBER_LOCATION)?)? {
+                Some(offset) => offset,
+                None => return Ok(None),
+            }
+        } else {
+            0
+        };
+
+        let ty = if die.has_attr_integrate(DwAt::TYPE)? {
+            Some(die.attr_integrate(DwAt::TYPE)?.get_die()?)
+        } else {
+            None
+        };
+        let type_size = match ty {
+            Some(ref ty) => ty.aggregate_size().unwrap_or(0),
+            None => 0,
+        };
+
+        let bitfield = die.has_attr(DwAt::BIT_SIZE)?;
+        let (bit_offset, bit_size) = if bitfield {
+            let bit_size = die.attr(DwAt::BIT_SIZE)?.get_unsigned()?;
+            let bit_offset = if die.has_attr(DwAt::DATA_BIT_OFFSET)? {
+                die.attr(DwAt::DATA_BIT_OFFSET)?.get_unsigned()?
+            } else if die.has_attr(DwAt::BIT_OFFSET)? {
+                // DWARF 2/3 count from the most significant bit of storage.
+                let storage = if die.has_attr(DwAt::BYTE_SIZE)? {
+                    die.attr(DwAt::BYTE_SIZE)?.get_unsigned()?
+                } else {
+                    type_size
+                };
+                let msb_offset = die.attr(DwAt::BIT_OFFSET)?.get_unsigned()?;
+                let bit_offset = if big_endian(unsafe { (*die.as_ptr()).cu }) {
+                    bits(byte_offset)?.checked_add(msb_offset)
+                } else {
+                    let storage_end = byte_offset
+                        .checked_add(storage)
+                        .ok_or_else(|| invalid("bit-field offset out of range"))?;
+                    bits(storage_end)?
+                        .checked_sub(msb_offset)
+                        .and_then(|end| end.checked_sub(bit_size))
+                };
+                bit_offset.ok_or_else(|| invalid("bit-field offset out of range"))?
+            } else {
+                bits(byte_offset)?
+            };
+            (bit_offset, bit_size)
+        } else {
+            (bits(byte_offset)?, bits(type_size)?)
+        };
+
+        Ok(Some(Member {
+            die,
+            ty,
+            bit_offset,
+            bit_size,
+            bitfield,
+        }))
+    }
+
+    /// Get the member DIE.
+    #[inline]
+    pub fn die(&self) -> &Die<'dw> {
+        &self.die
+    }
+
+    /// Get the member name, if it has one.
+    #[inline]
+    pub fn name(&self) -> Option<&'dw CStr> {
+        self.die.name().ok()
+    }
+
+    /// Get the member's type DIE.
+    #[inline]
+    pub fn type_die(&self) -> Option<&Die<'dw>> {
+        self.ty.as_ref()
+    }
+
+    /// Check whether this member is a base class.
+    #[inline]
+    pub fn is_inheritance(&self) -> bool {
+        matches!(self.die.tag(), Ok(DwTag::INHERITANCE))
+    }
+
+    /// Check whether this member is a bit-field.
+    #[inline]
+    pub fn is_bitfield(&self) -> bool {
+        self.bitfield
+    }
+
+    /// Get the offset in bytes, rounded down for bit-fields.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.bit_offset / 8
+    }
+
+    /// Get the size in bytes, rounded up for bit-fields.
+    #[inline]
+    pub fn size(&self) -> u64 {
+        self.bit_size.div_ceil(8)
+    }
+
+    /// Get the offset in bits from the start of the layout.
+    #[inline]
+    pub fn bit_offset(&self) -> u64 {
+        self.bit_offset
+    }
+
+    /// Get the size in bits.
+    #[inline]
+    pub fn bit_size(&self) -> u64 {
+        self.bit_size
+    }
+}
+
+impl Hole {
+    /// Get the offset in bytes, rounded down.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.bit_offset / 8
+    }
+
+    /// Get the number of whole bytes in this hole.
+    #[inline]
+    pub fn size(&self) -> u64 {
+        self.bit_size / 8
+    }
+
+    /// Get the offset in bits from the start of the layout.
+    #[inline]
+    pub fn bit_offset(&self) -> u64 {
+        self.bit_offset
+    }
+
+    /// Get the size in bits.
+    #[inline]
+    pub fn bit_size(&self) -> u64 {
+        self.bit_size
+    }
+}
+
+fn invalid(msg: &'static str) -> super::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg).into()
+}
+
+/// Convert a size or offset from bytes to bits, which may overflow for
+/// malformed DWARF.
+fn bits(bytes: u64) -> Result<u64> {
+    bytes
+        .checked_mul(8)
+        .ok_or_else(|| invalid("size or offset out of range"))
+}
+
+/// Decode `DW_AT_data_member_location`, either a constant or a simple
+/// location expression like `DW_OP_plus_uconst`.
+///
+/// Returns `None` for other expressions, like those of virtual base classes,
+/// which must be evaluated against an object at run time.
+fn member_location(attr: &Attribute<'_>) -> Result<Option<u64>> {
+    if let Ok(offset) = attr.get_unsigned() {
+        return Ok(Some(offset));
+    }
+
+    let mut expr = ptr::null_mut();
+    let mut len = 0;
+    ffi!(dwarf_getlocation(attr.as_ptr(), &mut expr, &mut len))?;
+    let ops = if expr.is_null() {
+        &[][..]
+    } else {
+        unsafe { slice::from_raw_parts(expr, len) }
+    };
+    match ops {
+        [op] if op.atom as u32 == ffi::DW_OP_plus_uconst || op.atom as u32 == ffi::DW_OP_constu => {
+            Ok(Some(op.number))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Layout;
+    use crate::test_util::current;
+    use crate::{Die, DwAt, DwTag, Result};
+
+    fn for_each_struct<F>(die: &Die<'_>, f: &mut F) -> Result<()>
+    where
+        F: FnMut(&Die<'_>),
+    {
+        if die.tag()? == DwTag::STRUCTURE_TYPE && !die.has_attr(DwAt::DECLARATION)? {
+            f(die);
+        }
+        for child in die.iter_children() {
+            for_each_struct(&child?, f)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn struct_layouts() {
+        let dw = current();
+        let mut count = 0;
+        for cu in dw.compile_units() {
+            let cu = cu.unwrap().get_die().unwrap();
+            for_each_struct(&cu, &mut |die| {
+                // Skip enums with variant parts, whose members overlap.
+                let variants = die
+                    .iter_children()
+                    .any(|c| c.unwrap().tag().unwrap() == DwTag::VARIANT_PART);
+                if variants {
+                    return;
+                }
+
+                let layout = Layout::new(die).unwrap();
+                let members = layout.members();
+                for (i, member) in members.iter().enumerate() {
+                    if i > 0 {
+                        assert!(members[i - 1].bit_offset() <= member.bit_offset());
+                    }
+                    if let Some(hole) = layout.hole_before(i) {
+                        assert!(hole.bit_size() > 0);
+                        assert_eq!(hole.bit_offset() + hole.bit_size(), member.bit_offset());
+                    }
+                }
+                count += 1;
+            })
+            .unwrap();
+        }
+        assert!(count > 0);
+    }
+}
@@ -8,14 +8,45 @@ pub mod raw {
 mod error;
 pub use crate::error::{Error, Result};
 
+mod constants;
+pub use crate::constants::{DwAt, DwAte, DwForm, DwLang, DwTag};
+
 mod dwarf;
 pub use crate::dwarf::Dwarf;
 
 mod units;
-pub use crate::units::{CompileUnit, CompileUnits, TypeUnit, TypeUnits};
+pub use crate::units::{
+    CompileUnit, CompileUnits, TypeUnit, TypeUnits, Unit, UnitHeader, UnitType, Units,
+};
 
 mod die;
-pub use crate::die::{Die, DieChildren};
+pub use crate::die::{Die, DieChildren, DieTree};
+
+mod abbrev;
+pub use crate::abbrev::{Abbrev, AbbrevAttr, Abbrevs, OffsetAbbrevs};
 
 mod attr;
-pub use crate::attr::{Attribute, AttributeValue};
+pub use crate::attr::{Attribute, AttributeClass, AttributeValue};
+
+mod lists;
+pub use crate::lists::{LocationListEntry, RangeListEntry, RawLocationList, RawRangeList};
+
+mod parents;
+pub use crate::parents::ParentIndex;
+
+mod scopes;
+pub use crate::scopes::Scopes;
+
+mod types;
+pub use crate::types::Dimension;
+
+pub mod layout;
+
+#[cfg(test)]
+mod test_util {
+    /// Open the DWARF of the test binary itself.
+    pub(crate) fn current() -> crate::Dwarf<'static> {
+        let exe = std::env::current_exe().unwrap();
+        crate::Dwarf::open(exe).unwrap()
+    }
+}
@@ -0,0 +1,649 @@
+use crate::ffi;
+
+use libelf::raw as elf;
+
+use std::ffi::CStr;
+use std::io;
+use std::mem;
+use std::ptr;
+use std::slice;
+
+use super::attr::big_endian;
+use super::Attribute;
+use super::AttributeClass;
+use super::Result;
+use super::{DwAt, DwForm};
+
+/// A raw entry from `.debug_rnglists` or `.debug_ranges`.
+///
+/// Addresses and indexes are exactly as encoded; nothing is resolved against
+/// the base address or `.debug_addr`.  Legacy `.debug_ranges` pairs are
+/// reported as `OffsetPair`, and their base address selection entries as
+/// `BaseAddress`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeListEntry {
+    /// `DW_RLE_base_addressx`: an index into `.debug_addr`.
+    BaseAddressx { index: u64 },
+    /// `DW_RLE_startx_endx`: indexes into `.debug_addr`.
+    StartxEndx { start: u64, end: u64 },
+    /// `DW_RLE_startx_length`: an index into `.debug_addr` and a length.
+    StartxLength { start: u64, length: u64 },
+    /// `DW_RLE_offset_pair`: offsets from the current base address.
+    OffsetPair { begin: u64, end: u64 },
+    /// `DW_RLE_base_address`: a new base address.
+    BaseAddress { address: u64 },
+    /// `DW_RLE_start_end`: absolute addresses.
+    StartEnd { begin: u64, end: u64 },
+    /// `DW_RLE_start_length`: an absolute address and a length.
+    StartLength { begin: u64, length: u64 },
+}
+
+/// A raw entry from `.debug_loclists` or `.debug_loc`.
+///
+/// Like `RangeListEntry`, with the location expression of each bounded
+/// entry.  The GNU split DWARF 4 encoding in `.debug_loc.dwo` is reported
+/// with the equivalent indexed kinds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocationListEntry<'dw> {
+    /// `DW_LLE_base_addressx`: an index into `.debug_addr`.
+    BaseAddressx { index: u64 },
+    /// `DW_LLE_startx_endx`: indexes into `.debug_addr`.
+    StartxEndx {
+        start: u64,
+        end: u64,
+        expr: &'dw [u8],
+    },
+    /// `DW_LLE_startx_length`: an index into `.debug_addr` and a length.
+    StartxLength {
+        start: u64,
+        length: u64,
+        expr: &'dw [u8],
+    },
+    /// `DW_LLE_offset_pair`: offsets from the current base address.
+    OffsetPair {
+        begin: u64,
+        end: u64,
+        expr: &'dw [u8],
+    },
+    /// `DW_LLE_default_location`: applies where no other entry does.
+    DefaultLocation { expr: &'dw [u8] },
+    /// `DW_LLE_base_address`: a new base address.
+    BaseAddress { address: u64 },
+    /// `DW_LLE_start_end`: absolute addresses.
+    StartEnd {
+        begin: u64,
+        end: u64,
+        expr: &'dw [u8],
+    },
+    /// `DW_LLE_start_length`: an absolute address and a length.
+    StartLength {
+        begin: u64,
+        length: u64,
+        expr: &'dw [u8],
+    },
+    /// `DW_LLE_GNU_view_pair`: location views for the following entry.
+    ViewPair { begin: u64, end: u64 },
+}
+
+/// An iterator over the raw entries of a range list.
+///
+/// GNU split DWARF 4 units are not supported, since their ranges are in the
+/// skeleton's `.debug_ranges`; use `Die::ranges` for those.
+#[derive(Debug, Clone)]
+pub struct RawRangeList<'dw> {
+    reader: Reader<'dw>,
+    format: Format,
+    finished: bool,
+}
+
+impl<'dw> RawRangeList<'dw> {
+    pub(crate) fn new(attr: &Attribute<'dw>) -> Result<RawRangeList<'dw>> {
+        let (reader, format) = open(attr, AttributeClass::RngList)?;
+        Ok(RawRangeList {
+            reader,
+            format,
+            finished: false,
+        })
+    }
+
+    /// The section offset of the next entry.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.reader.offset as u64
+    }
+}
+
+impl<'dw> Iterator for RawRangeList<'dw> {
+    type Item = Result<RangeListEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use self::LocationListEntry as L;
+        use self::RangeListEntry as R;
+        if self.finished {
+            return None;
+        }
+        let entry = match next_entry(&mut self.reader, self.format, false) {
+            Ok(Some(entry)) => entry,
+            Ok(None) => {
+                self.finished = true;
+                return None;
+            }
+            Err(e) => {
+                self.finished = true;
+                return Some(Err(e));
+            }
+        };
+        let entry = match entry {
+            L::BaseAddressx { index } => R::BaseAddressx { index },
+            L::StartxEndx { start, end, .. } => R::StartxEndx { start, end },
+            L::StartxLength { start, length, .. } => R::StartxLength { start, length },
+            L::OffsetPair { begin, end, .. } => R::OffsetPair { begin, end },
+            L::BaseAddress { address } => R::BaseAddress { address },
+            L::StartEnd { begin, end, .. } => R::StartEnd { begin, end },
+            L::StartLength { begin, length, .. } => R::StartLength { begin, length },
+            L::DefaultLocation { .. } | L::ViewPair { .. } => {
+                self.finished = true;
+                return Some(Err(invalid("location entry in a range list")));
+            }
+        };
+        Some(Ok(entry))
+    }
+}
+
+/// An iterator over the raw entries of a location list.
+#[derive(Debug, Clone)]
+pub struct RawLocationList<'dw> {
+    reader: Reader<'dw>,
+    format: Format,
+    finished: bool,
+}
+
+impl<'dw> RawLocationList<'dw> {
+    pub(crate) fn new(attr: &Attribute<'dw>) -> Result<RawLocationList<'dw>> {
+        let (reader, format) = open(attr, AttributeClass::LocList)?;
+        Ok(RawLocationList {
+            reader,
+            format,
+            finished: false,
+        })
+    }
+
+    /// The section offset of the next entry.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.reader.offset as u64
+    }
+}
+
+impl<'dw> Iterator for RawLocationList<'dw> {
+    type Item = Result<LocationListEntry<'dw>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        match next_entry(&mut self.reader, self.format, true) {
+            Ok(Some(entry)) => Some(Ok(entry)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// The encoding of list entries in a section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// DWARF 2-4 `.debug_ranges` and `.debug_loc` address pairs.
+    Legacy,
+    /// GNU split DWARF 4 `.debug_loc.dwo` entries.
+    GnuSplit,
+    /// DWARF 5 `.debug_rnglists` and `.debug_loclists` entries.
+    Lists,
+}
+
+/// Decode the next entry, or `None` at the end of the list.
+///
+/// Range entries are decoded into their location counterparts, with empty
+/// expressions.
+fn next_entry<'dw>(
+    reader: &mut Reader<'dw>,
+    format: Format,
+    location: bool,
+) -> Result<Option<LocationListEntry<'dw>>> {
+    use self::LocationListEntry as L;
+    let expr = |reader: &mut Reader<'dw>| -> Result<&'dw [u8]> {
+        if !location {
+            return Ok(&[]);
+        }
+        let len = match format {
+            Format::Lists => reader.uleb()?,
+            _ => reader.uint(2)?,
+        };
+        reader.bytes(len)
+    };
+
+    let entry = match format {
+        Format::Legacy => {
+            let begin = reader.address()?;
+            let end = reader.address()?;
+            if begin == 0 && end == 0 {
+                return Ok(None);
+            }
+            if begin == reader.max_address() {
+                L::BaseAddress { address: end }
+            } else {
+                let expr = expr(reader)?;
+                L::OffsetPair { begin, end, expr }
+            }
+        }
+
+        Format::GnuSplit => match u32::from(reader.uint(1)? as u8) {
+            ffi::DW_LLE_GNU_end_of_list_entry => return Ok(None),
+            ffi::DW_LLE_GNU_base_address_selection_entry => L::BaseAddressx {
+                index: reader.uleb()?,
+            },
+            ffi::DW_LLE_GNU_start_end_entry => {
+                let start = reader.uleb()?;
+                let end = reader.uleb()?;
+                let expr = expr(reader)?;
+                L::StartxEndx { start, end, expr }
+            }
+            ffi::DW_LLE_GNU_start_length_entry => {
+                let start = reader.uleb()?;
+                let length = reader.uint(4)?;
+                let expr = expr(reader)?;
+                L::StartxLength {
+                    start,
+                    length,
+                    expr,
+                }
+            }
+            _ => return Err(invalid("unknown location list entry kind")),
+        },
+
+        Format::Lists if location => match u32::from(reader.uint(1)? as u8) {
+            ffi::DW_LLE_end_of_list => return Ok(None),
+            ffi::DW_LLE_base_addressx => L::BaseAddressx {
+                index: reader.uleb()?,
+            },
+            ffi::DW_LLE_startx_endx => {
+                let start = reader.uleb()?;
+                let end = reader.uleb()?;
+                let expr = expr(reader)?;
+                L::StartxEndx { start, end, expr }
+            }
+            ffi::DW_LLE_startx_length => {
+                let start = reader.uleb()?;
+                let length = reader.uleb()?;
+                let expr = expr(reader)?;
+                L::StartxLength {
+                    start,
+                    length,
+                    expr,
+                }
+            }
+            ffi::DW_LLE_offset_pair => {
+                let begin = reader.uleb()?;
+                let end = reader.uleb()?;
+                let expr = expr(reader)?;
+                L::OffsetPair { begin, end, expr }
+            }
+            ffi::DW_LLE_default_location => L::DefaultLocation {
+                expr: expr(reader)?,
+            },
+            ffi::DW_LLE_base_address => L::BaseAddress {
+                address: reader.address()?,
+            },
+            ffi::DW_LLE_start_end => {
+                let begin = reader.address()?;
+                let end = reader.address()?;
+                let expr = expr(reader)?;
+                L::StartEnd { begin, end, expr }
+            }
+            ffi::DW_LLE_start_length => {
+                let begin = reader.address()?;
+                let length = reader.uleb()?;
+                let expr = expr(reader)?;
+                L::StartLength {
+                    begin,
+                    length,
+                    expr,
+                }
+            }
+            ffi::DW_LLE_GNU_view_pair => {
+                let begin = reader.uleb()?;
+                let end = reader.uleb()?;
+                L::ViewPair { begin, end }
+            }
+            _ => return Err(invalid("unknown location list entry kind")),
+        },
+
+        Format::Lists => match u32::from(reader.uint(1)? as u8) {
+            ffi::DW_RLE_end_of_list => return Ok(None),
+            ffi::DW_RLE_base_addressx => L::BaseAddressx {
+                index: reader.uleb()?,
+            },
+            ffi::DW_RLE_startx_endx => L::StartxEndx {
+                start: reader.uleb()?,
+                end: reader.uleb()?,
+                expr: &[],
+            },
+            ffi::DW_RLE_startx_length => L::StartxLength {
+                start: reader.uleb()?,
+                length: reader.uleb()?,
+                expr: &[],
+            },
+            ffi::DW_RLE_offset_pair => L::OffsetPair {
+                begin: reader.uleb()?,
+                end: reader.uleb()?,
+                expr: &[],
+            },
+            ffi::DW_RLE_base_address => L::BaseAddress {
+                address: reader.address()?,
+            },
+            ffi::DW_RLE_start_end => L::StartEnd {
+                begin: reader.address()?,
+                end: reader.address()?,
+                expr: &[],
+            },
+            ffi::DW_RLE_start_length => L::StartLength {
+                begin: reader.address()?,
+                length: reader.uleb()?,
+                expr: &[],
+            },
+            _ => return Err(invalid("unknown range list entry kind")),
+        },
+    };
+    Ok(Some(entry))
+}
+
+/// Find the section and starting offset of the list named by `attr`.
+fn open<'dw>(attr: &Attribute<'dw>, class: AttributeClass) -> Result<(Reader<'dw>, Format)> {
+    if attr.class()? != Some(class) {
+        let e = io::Error::new(io::ErrorKind::InvalidInput, "not a list attribute");
+        return Err(e.into());
+    }
+
+    let cu = unsafe { (*attr.as_ptr()).cu };
+    let mut version = 0;
+    let mut unit_type = 0;
+    let mut address_size = 0;
+    let mut offset_size = 0;
+    let cudie = super::Die::default();
+    ffi!(dwarf_cu_info(
+        cu,
+        &mut version,
+        &mut unit_type,
+        cudie.as_ptr(),
+        ptr::null_mut(),
+        ptr::null_mut(),
+        &mut address_size,
+        &mut offset_size
+    ))?;
+
+    let name = match (class, version >= 5) {
+        (AttributeClass::RngList, true) => "rnglists",
+        (AttributeClass::RngList, false) => "ranges",
+        (_, true) => "loclists",
+        (_, false) => "loc",
+    };
+    let dwarf = unsafe { ffi::dwarf_cu_getdwarf(cu) };
+    let (data, dwo) = match section_data(dwarf, &format!(".debug_{}", name))? {
+        Some(data) => (data, false),
+        None => match section_data(dwarf, &format!(".debug_{}.dwo", name))? {
+            Some(data) => (data, true),
+            // GNU split DWARF 4 ranges live in the skeleton's file, which
+            // libdw doesn't expose from a split unit.
+            None if class == AttributeClass::RngList
+                && version < 5
+                && u32::from(unit_type) == ffi::DW_UT_split_compile =>
+            {
+                return Err(invalid("split DWARF 4 range lists are not supported"));
+            }
+            None => return Err(invalid("missing list section")),
+        },
+    };
+    let format = if version >= 5 {
+        Format::Lists
+    } else if dwo && class == AttributeClass::LocList {
+        Format::GnuSplit
+    } else {
+        Format::Legacy
+    };
+
+    let mut reader = Reader {
+        data,
+        offset: 0,
+        big_endian: big_endian(cu),
+        address_size,
+    };
+
+    let offset = match attr.form() {
+        form @ (DwForm::RNGLISTX | DwForm::LOCLISTX) => {
+            // The index selects an offset relative to the unit's base,
+            // which defaults to just past the first section header.
+            let base_name = if form == DwForm::RNGLISTX {
+                DwAt::RNGLISTS_BASE
+            } else {
+                DwAt::LOCLISTS_BASE
+            };
+            let base = match cudie.attr(base_name) {
+                Ok(base) => base.get_unsigned()?,
+                Err(_) if offset_size == 8 => 20,
+                Err(_) => 12,
+            };
+            let index = attr.get_unsigned()?;
+            reader.seek(
+                index
+                    .saturating_mul(offset_size.into())
+                    .saturating_add(base),
+            )?;
+            base.saturating_add(reader.uint(offset_size)?)
+        }
+        _ => attr.get_unsigned()?,
+    };
+    reader.seek(offset)?;
+    Ok((reader, format))
+}
+
+/// Get the contents of the named section in the file of `dwarf`.
+pub(crate) fn section_data<'dw>(dwarf: *mut ffi::Dwarf, name: &str) -> Result<Option<&'dw [u8]>> {
+    unsafe {
+        let elf = ffi::dwarf_getelf(dwarf);
+        let mut shstrndx = 0;
+        if elf.is_null() || elf::elf_getshdrstrndx(elf, &mut shstrndx) != 0 {
+            return Ok(None);
+        }
+
+        let mut scn = ptr::null_mut();
+        loop {
+            scn = elf::elf_nextscn(elf, scn);
+            if scn.is_null() {
+                return Ok(None);
+            }
+
+            let mut shdr = mem::zeroed();
+            if elf::gelf_getshdr(scn, &mut shdr).is_null() {
+                continue;
+            }
+            let sname = elf::elf_strptr(elf, shstrndx, shdr.sh_name as usize);
+            if sname.is_null() || CStr::from_ptr(sname).to_bytes() != name.as_bytes() {
+                continue;
+            }
+
+            // libdw normally decompresses the sections it uses, but be sure.
+            if shdr.sh_flags & u64::from(elf::SHF_COMPRESSED) != 0
+                && elf::elf_compress(scn, 0, 0) < 0
+            {
+                return Err(invalid("cannot decompress section"));
+            }
+
+            let data = elf::elf_getdata(scn, ptr::null_mut());
+            if data.is_null() || (*data).d_buf.is_null() {
+                return Ok(Some(&[]));
+            }
+            let data = slice::from_raw_parts((*data).d_buf as *const u8, (*data).d_size);
+            return Ok(Some(data));
+        }
+    }
+}
+
+fn invalid(msg: &'static str) -> super::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg).into()
+}
+
+/// A bounds-checked cursor over section data.
+#[derive(Debug, Clone)]
+struct Reader<'dw> {
+    data: &'dw [u8],
+    offset: usize,
+    big_endian: bool,
+    address_size: u8,
+}
+
+impl<'dw> Reader<'dw> {
+    fn seek(&mut self, offset: u64) -> Result<()> {
+        match usize::try_from(offset) {
+            Ok(offset) if offset <= self.data.len() => {
+                self.offset = offset;
+                Ok(())
+            }
+            _ => Err(invalid("list offset out of range")),
+        }
+    }
+
+    fn bytes(&mut self, len: u64) -> Result<&'dw [u8]> {
+        let end = usize::try_from(len)
+            .ok()
+            .and_then(|len| self.offset.checked_add(len))
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| invalid("truncated list entry"))?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn uint(&mut self, size: u8) -> Result<u64> {
+        if size == 0 || size > 8 {
+            return Err(invalid("unsupported integer size"));
+        }
+        let bytes = self.bytes(size.into())?;
+        let fold = |value, &byte| value << 8 | u64::from(byte);
+        if self.big_endian {
+            Ok(bytes.iter().fold(0, fold))
+        } else {
+            Ok(bytes.iter().rev().fold(0, fold))
+        }
+    }
+
+    fn uleb(&mut self) -> Result<u64> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.uint(1)?;
+            if shift < 64 {
+                value |= (byte & 0x7f) << shift;
+            }
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn address(&mut self) -> Result<u64> {
+        self.uint(self.address_size)
+    }
+
+    fn max_address(&self) -> u64 {
+        u64::MAX >> (64 - 8 * u32::from(self.address_size.min(8)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Format, LocationListEntry, RangeListEntry, Reader};
+    use crate::{AttributeClass, Die, Dwarf};
+
+    #[test]
+    fn decode_entries() {
+        let data = [
+            0x01, 0x02, // base_addressx 2
+            0x04, 0x10, 0x20, 0x02, 0x30, 0x9f, // offset_pair with expr
+            0x05, 0x01, 0x50, // default_location
+            0x09, 0x00, 0x01, // view_pair
+            0x00, // end_of_list
+        ];
+        let mut reader = Reader {
+            data: &data,
+            offset: 0,
+            big_endian: false,
+            address_size: 8,
+        };
+        let mut entries = vec![];
+        while let Some(entry) = super::next_entry(&mut reader, Format::Lists, true).unwrap() {
+            entries.push(entry);
+        }
+        assert_eq!(
+            entries,
+            [
+                LocationListEntry::BaseAddressx { index: 2 },
+                LocationListEntry::OffsetPair {
+                    begin: 0x10,
+                    end: 0x20,
+                    expr: &[0x30, 0x9f],
+                },
+                LocationListEntry::DefaultLocation { expr: &[0x50] },
+                LocationListEntry::ViewPair { begin: 0, end: 1 },
+            ]
+        );
+        assert_eq!(reader.offset, data.len());
+
+        // Location-only kinds are not valid in range lists.
+        reader.offset = 11;
+        assert!(super::next_entry(&mut reader, Format::Lists, false).is_err());
+    }
+
+    #[test]
+    fn self_lists() {
+        fn check(die: &Die<'_>, count: &mut usize) {
+            for attr in die.attrs().unwrap() {
+                match attr.class().unwrap() {
+                    Some(AttributeClass::RngList) => {
+                        let entries = attr.raw_range_list().unwrap();
+                        for entry in entries {
+                            if let RangeListEntry::StartEnd { begin, end } = entry.unwrap() {
+                                assert!(begin <= end);
+                            }
+                        }
+                        *count += 1;
+                    }
+                    Some(AttributeClass::LocList) => {
+                        attr.raw_location_list().unwrap().for_each(|e| {
+                            e.unwrap();
+                        });
+                        *count += 1;
+                    }
+                    _ => (),
+                }
+            }
+            for child in die.iter_children() {
+                check(&child.unwrap(), count);
+            }
+        }
+
+        let exe = ::std::env::current_exe().unwrap();
+        let dw = Dwarf::open(exe).unwrap();
+        let mut count = 0;
+        for cu in dw.compile_units().take(50) {
+            check(&cu.unwrap().get_die().unwrap(), &mut count);
+        }
+        assert!(count > 0);
+    }
+}
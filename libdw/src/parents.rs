@@ -0,0 +1,147 @@
+use crate::ffi;
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr;
+use std::rc::Rc;
+
+use super::types;
+use super::Die;
+use super::Dwarf;
+use super::Result;
+
+/// A map from DIEs to their parents, since libdw only links DIEs downward.
+///
+/// Each unit is indexed on first use, and kept for as long as this index.
+/// Build one and keep it for repeated lookups; the `Die` methods of the same
+/// names index their unit anew on every call.
+pub struct ParentIndex<'dw> {
+    units: RefCell<HashMap<usize, Rc<UnitParents>>>,
+    phantom: PhantomData<&'dw Dwarf<'dw>>,
+}
+
+/// The parents of every DIE in one unit.
+struct UnitParents {
+    // Sorted by child offset, which is how a depth-first walk finds them.
+    entries: Vec<(ffi::Dwarf_Off, ffi::Dwarf_Die)>,
+}
+
+impl<'dw> fmt::Debug for ParentIndex<'dw> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ParentIndex")
+            .field("units", &self.units.borrow().len())
+            .finish()
+    }
+}
+
+impl<'dw> Default for ParentIndex<'dw> {
+    #[inline]
+    fn default() -> Self {
+        ParentIndex::new()
+    }
+}
+
+impl<'dw> ParentIndex<'dw> {
+    #[inline]
+    pub fn new() -> ParentIndex<'dw> {
+        ParentIndex {
+            units: RefCell::default(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Get the parent of `die`, indexing its unit on first use.
+    ///
+    /// This is the lexical parent; see `ancestors` to follow declarations.
+    pub fn parent(&self, die: &Die<'dw>) -> Result<Option<Die<'dw>>> {
+        // Every unit of a `Die<'dw>` lives for `'dw`, so its address can't be
+        // reused by another unit while this index exists.
+        let cu = unsafe { (*die.as_ptr()).cu } as usize;
+        let cached = self.units.borrow().get(&cu).cloned();
+        let unit = match cached {
+            Some(unit) => unit,
+            None => {
+                let unit = Rc::new(UnitParents::build(die)?);
+                self.units.borrow_mut().insert(cu, Rc::clone(&unit));
+                unit
+            }
+        };
+        Ok(unit.parent(die))
+    }
+
+    /// Get the enclosing scopes of `die`, starting from the unit DIE.
+    ///
+    /// Where a DIE has a `DW_AT_specification` or `DW_AT_abstract_origin`,
+    /// the scopes of that declaration are used instead, so an out-of-line
+    /// method definition is still found within its class.
+    pub fn ancestors(&self, die: &Die<'dw>) -> Result<Vec<Die<'dw>>> {
+        let mut ancestors = Vec::new();
+        let mut visited = HashSet::new();
+        let mut die = die.clone();
+        loop {
+            die = die.declaration()?;
+            // A malformed declaration may lead back into its own scope.
+            let cu = unsafe { (*die.as_ptr()).cu } as usize;
+            if !visited.insert((cu, die.offset())) {
+                break;
+            }
+            match self.parent(&die)? {
+                Some(parent) => {
+                    ancestors.push(parent.clone());
+                    die = parent;
+                }
+                None => break,
+            }
+        }
+        ancestors.reverse();
+        Ok(ancestors)
+    }
+
+    /// Get the name of `die`, prefixed by its enclosing namespaces and
+    /// types, like `ns::Class::method`.
+    pub fn qualified_name(&self, die: &Die<'dw>) -> Result<String> {
+        let name = die.name()?.to_string_lossy();
+        types::qualify(&self.ancestors(die)?, &name)
+    }
+}
+
+impl UnitParents {
+    /// Index the whole unit containing `die`.
+    fn build(die: &Die<'_>) -> Result<UnitParents> {
+        let cudie = Die::default();
+        ffi!(dwarf_cu_die(
+            (*die.as_ptr()).cu,
+            cudie.as_ptr(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut()
+        ))?;
+
+        let mut entries = Vec::new();
+        let mut tree = cudie.tree();
+        while let Some(child) = tree.next() {
+            let child = child?;
+            if let Some(parent) = tree.parent() {
+                entries.push((child.offset(), unsafe { *parent.as_ptr() }));
+            }
+        }
+        Ok(UnitParents { entries })
+    }
+
+    /// Find the parent of `die`, which must be in this unit.
+    fn parent<'dw>(&self, die: &Die<'dw>) -> Option<Die<'dw>> {
+        let offset = die.offset();
+        let index = self
+            .entries
+            .binary_search_by_key(&offset, |&(child, _)| child)
+            .ok()?;
+        let parent = Die::default();
+        unsafe { *parent.as_ptr() = self.entries[index].1 };
+        Some(parent)
+    }
+}
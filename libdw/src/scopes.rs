@@ -0,0 +1,204 @@
+use crate::ffi;
+
+use std::ffi::CStr;
+use std::fmt;
+use std::ops::Deref;
+use std::ptr;
+use std::slice;
+
+use super::Die;
+use super::DwTag;
+use super::{Error, Result};
+
+/// A chain of scope DIEs, from the innermost scope out to the compile unit.
+///
+/// Typical scopes are lexical blocks, inlined subroutines, subprograms, and
+/// finally the unit itself.  The list dereferences to a slice of `Die`.
+pub struct Scopes<'dw> {
+    inner: *mut Die<'dw>,
+    len: usize,
+}
+
+impl<'dw> Scopes<'dw> {
+    #[inline]
+    fn new(scopes: *mut ffi::Dwarf_Die, len: libc::c_int) -> Scopes<'dw> {
+        Scopes {
+            inner: scopes as *mut Die<'dw>,
+            len: len as usize,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn from_pc(cudie: &Die<'dw>, pc: u64) -> Result<Scopes<'dw>> {
+        let mut scopes = ptr::null_mut();
+        let len = ffi!(dwarf_getscopes(cudie.as_ptr(), pc, &mut scopes))?;
+        Ok(Scopes::new(scopes, len))
+    }
+
+    #[inline]
+    pub(crate) fn from_die(die: &Die<'dw>) -> Result<Scopes<'dw>> {
+        let mut scopes = ptr::null_mut();
+        let len = ffi!(dwarf_getscopes_die(die.as_ptr(), &mut scopes))?;
+        Ok(Scopes::new(scopes, len))
+    }
+
+    /// Get the chain of functions containing the innermost scope.
+    ///
+    /// This starts with the innermost `DW_TAG_inlined_subroutine`, if any, and
+    /// continues through each caller it was inlined into, ending with the
+    /// concrete `DW_TAG_subprogram`.  The call site of each inlined function is
+    /// available from its `call_file`, `call_line`, and `call_column`.
+    pub fn inline_chain(&self) -> Result<Vec<Die<'dw>>> {
+        let innermost = match self.first() {
+            Some(die) => die,
+            None => return Ok(Vec::new()),
+        };
+
+        // The PC-based scopes jump from an inlined instance to the scopes of
+        // its abstract definition, so walk the concrete parents instead.  An
+        // entry point has no call site, so it is left to its subprogram.
+        let mut chain = Vec::new();
+        for die in Scopes::from_die(innermost)?.iter() {
+            match die.tag()? {
+                DwTag::INLINED_SUBROUTINE => chain.push(die.clone()),
+                DwTag::SUBPROGRAM => {
+                    chain.push(die.clone());
+                    break;
+                }
+                _ => (),
+            }
+        }
+        Ok(chain)
+    }
+
+    /// Search the scopes in order for a variable or parameter called `name`.
+    ///
+    /// Returns the index of the scope where it was found, and its DIE.
+    pub fn find_variable(&self, name: &CStr) -> Result<Option<(usize, Die<'dw>)>> {
+        let die = Die::default();
+        let rc = raw_ffi!(dwarf_getscopevar(
+            self.inner as *mut ffi::Dwarf_Die,
+            self.len as libc::c_int,
+            name.as_ptr(),
+            0,
+            ptr::null(),
+            0,
+            0,
+            die.as_ptr()
+        ));
+        match rc {
+            -2 => Ok(None),
+            rc if rc < 0 => Err(Error::last()),
+            index => Ok(Some((index as usize, die))),
+        }
+    }
+}
+
+impl<'dw> Deref for Scopes<'dw> {
+    type Target = [Die<'dw>];
+
+    #[inline]
+    fn deref(&self) -> &[Die<'dw>] {
+        if self.inner.is_null() {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(self.inner, self.len) }
+        }
+    }
+}
+
+impl<'dw> fmt::Debug for Scopes<'dw> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'dw> Drop for Scopes<'dw> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { libc::free(self.inner as *mut libc::c_void) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::current;
+    use crate::{Die, DwTag, Dwarf};
+
+    // Find some function with a low_pc and a named parameter.
+    fn function_with_param<'dw>(dw: &'dw Dwarf<'dw>) -> Option<(Die<'dw>, Die<'dw>)> {
+        for cu in dw.compile_units() {
+            let mut found = None;
+            cu.unwrap()
+                .get_die()
+                .unwrap()
+                .for_each_func(|func| {
+                    if func.low_pc().is_ok() {
+                        for child in func.iter_children() {
+                            let child = child?;
+                            if child.tag()? == DwTag::FORMAL_PARAMETER && child.name().is_ok() {
+                                found = Some((func.clone(), child));
+                                return Ok(false);
+                            }
+                        }
+                    }
+                    Ok(true)
+                })
+                .unwrap();
+            if found.is_some() {
+                return found;
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn scopes_at_function() {
+        // NB: libdw doesn't descend into namespaces when matching a PC, so
+        // Rust functions may not be found at all.  Just check consistency.
+        let dw = current();
+        let (func, _) = function_with_param(&dw).unwrap();
+        let pc = func.low_pc().unwrap();
+        let scopes = dw.scopes_at(pc).unwrap();
+        for scope in scopes.iter() {
+            assert!(scope.has_pc(pc).unwrap());
+        }
+        if let Some(last) = scopes.last() {
+            assert_eq!(last.offset(), func.unit().unwrap().offset());
+        }
+    }
+
+    #[test]
+    fn scopes_of_die() {
+        let dw = current();
+        let (func, param) = function_with_param(&dw).unwrap();
+        let scopes = param.scopes().unwrap();
+        assert_eq!(scopes[0].offset(), param.offset());
+        assert_eq!(scopes[1].offset(), func.offset());
+    }
+
+    #[test]
+    fn inline_chain() {
+        let dw = current();
+        let (func, param) = function_with_param(&dw).unwrap();
+        let chain = param.scopes().unwrap().inline_chain().unwrap();
+        assert_eq!(chain.len(), 1);
+        assert_eq!(chain[0].offset(), func.offset());
+    }
+
+    #[test]
+    fn find_param() {
+        let dw = current();
+        let (func, param) = function_with_param(&dw).unwrap();
+        let scopes = func.scopes().unwrap();
+        let (index, var) = scopes
+            .find_variable(param.name().unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(scopes[index].offset(), func.offset());
+        assert_eq!(var.name().unwrap(), param.name().unwrap());
+
+        let missing = c"no such variable";
+        assert!(scopes.find_variable(missing).unwrap().is_none());
+    }
+}
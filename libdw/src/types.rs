@@ -0,0 +1,386 @@
+use crate::ffi;
+
+use super::Die;
+use super::Result;
+use super::{DwAt, DwLang, DwTag};
+
+/// One dimension of an array type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dimension {
+    lower_bound: i64,
+    count: Option<u64>,
+}
+
+impl Dimension {
+    /// The first valid index, explicit or the language default.
+    #[inline]
+    pub fn lower_bound(&self) -> i64 {
+        self.lower_bound
+    }
+
+    /// The last valid index, if the dimension is bounded.
+    #[inline]
+    pub fn upper_bound(&self) -> Option<i64> {
+        self.count
+            .map(|count| self.lower_bound.wrapping_add(count as i64).wrapping_sub(1))
+    }
+
+    /// The number of elements, if the dimension is bounded.
+    #[inline]
+    pub fn count(&self) -> Option<u64> {
+        self.count
+    }
+}
+
+pub(crate) fn array_dimensions(array: &Die<'_>) -> Result<Vec<Dimension>> {
+    let mut default_lower = None;
+    let mut dims = Vec::new();
+    for child in array.iter_children() {
+        let child = child?;
+        if child.tag()? != DwTag::SUBRANGE_TYPE {
+            continue;
+        }
+
+        let lower_bound = if child.has_attr_integrate(DwAt::LOWER_BOUND)? {
+            child.attr_integrate(DwAt::LOWER_BOUND)?.get_signed()?
+        } else if let Some(lower) = default_lower {
+            lower
+        } else {
+            let lang = array.unit()?.source_language()?;
+            let mut lower = 0;
+            ffi!(dwarf_default_lower_bound(lang.0.into(), &mut lower))?;
+            *default_lower.get_or_insert(lower)
+        };
+
+        // Dynamic bounds, like a DWARF expression, are left unknown.
+        let count = if child.has_attr_integrate(DwAt::COUNT)? {
+            child.attr_integrate(DwAt::COUNT)?.get_unsigned().ok()
+        } else if child.has_attr_integrate(DwAt::UPPER_BOUND)? {
+            let upper = child.attr_integrate(DwAt::UPPER_BOUND)?.get_signed().ok();
+            upper.map(|upper| upper.wrapping_sub(lower_bound).wrapping_add(1) as u64)
+        } else {
+            None
+        };
+
+        dims.push(Dimension { lower_bound, count });
+    }
+    Ok(dims)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Style {
+    C,
+    Cxx,
+    Rust,
+}
+
+impl Style {
+    fn of(die: &Die<'_>) -> Style {
+        let lang = die.unit().and_then(|cu| cu.source_language());
+        match lang {
+            Ok(DwLang::C_PLUS_PLUS)
+            | Ok(DwLang::C_PLUS_PLUS_03)
+            | Ok(DwLang::C_PLUS_PLUS_11)
+            | Ok(DwLang::C_PLUS_PLUS_14)
+            | Ok(DwLang::OBJC_PLUS_PLUS) => Style::Cxx,
+            Ok(DwLang::RUST) => Style::Rust,
+            // Other and unknown languages get plain C names.
+            _ => Style::C,
+        }
+    }
+}
+
+pub(crate) fn type_name(die: &Die<'_>) -> Result<String> {
+    match Style::of(die) {
+        Style::Rust => rust_name(Some(die)),
+        style => c_declare(style, Some(die), String::new()),
+    }
+}
+
+fn type_of<'dw>(die: &Die<'dw>) -> Result<Option<Die<'dw>>> {
+    if die.has_attr_integrate(DwAt::TYPE)? {
+        let attr = die.attr_integrate(DwAt::TYPE)?;
+        Ok(Some(attr.get_die()?))
+    } else {
+        Ok(None)
+    }
+}
+
+fn is_pointer(die: &Die<'_>) -> Result<bool> {
+    let pointer = match die.tag()? {
+        DwTag::POINTER_TYPE
+        | DwTag::REFERENCE_TYPE
+        | DwTag::RVALUE_REFERENCE_TYPE
+        | DwTag::PTR_TO_MEMBER_TYPE => !die.has_attr(DwAt::NAME)?,
+        _ => false,
+    };
+    Ok(pointer)
+}
+
+fn join(base: &str, decl: &str) -> String {
+    if decl.is_empty() {
+        base.into()
+    } else {
+        format!("{} {}", base, decl)
+    }
+}
+
+// A declarator with a prefix operator must be grouped before a suffix.
+fn group(decl: String) -> String {
+    if decl.is_empty() || decl.starts_with('[') || decl.starts_with('(') {
+        decl
+    } else {
+        format!("({})", decl)
+    }
+}
+
+/// Render a C-style declaration of `decl` with the given type, inside out.
+fn c_declare(style: Style, ty: Option<&Die<'_>>, decl: String) -> Result<String> {
+    let ty = match ty {
+        Some(ty) => ty,
+        None => return Ok(join("void", &decl)),
+    };
+
+    let tag = ty.tag()?;
+    let named = ty.has_attr(DwAt::NAME)?;
+    match tag {
+        DwTag::POINTER_TYPE | DwTag::REFERENCE_TYPE | DwTag::RVALUE_REFERENCE_TYPE if !named => {
+            let sigil = match tag {
+                DwTag::POINTER_TYPE => "*",
+                DwTag::REFERENCE_TYPE => "&",
+                _ => "&&",
+            };
+            c_declare(style, type_of(ty)?.as_ref(), format!("{}{}", sigil, decl))
+        }
+
+        DwTag::PTR_TO_MEMBER_TYPE if !named => {
+            let class = ty.attr_integrate(DwAt::CONTAINING_TYPE)?.get_die()?;
+            let decl = format!("{}::*{}", c_named(style, &class)?, decl);
+            c_declare(style, type_of(ty)?.as_ref(), decl)
+        }
+
+        DwTag::CONST_TYPE | DwTag::VOLATILE_TYPE | DwTag::RESTRICT_TYPE | DwTag::ATOMIC_TYPE => {
+            let qualifier = match tag {
+                DwTag::CONST_TYPE => "const",
+                DwTag::VOLATILE_TYPE => "volatile",
+                DwTag::RESTRICT_TYPE => "restrict",
+                _ => "_Atomic",
+            };
+            let target = type_of(ty)?;
+            match target {
+                // Qualifiers bind to the pointer on its right, "T *const p".
+                Some(ref target) if is_pointer(target)? => {
+                    c_declare(style, Some(target), join(qualifier, &decl))
+                }
+                _ => Ok(format!(
+                    "{} {}",
+                    qualifier,
+                    c_declare(style, target.as_ref(), decl)?
+                )),
+            }
+        }
+
+        DwTag::ARRAY_TYPE if !named => {
+            let mut decl = group(decl);
+            for dim in array_dimensions(ty)? {
+                match dim.count() {
+                    Some(count) => decl.push_str(&format!("[{}]", count)),
+                    None => decl.push_str("[]"),
+                }
+            }
+            c_declare(style, type_of(ty)?.as_ref(), decl)
+        }
+
+        DwTag::SUBROUTINE_TYPE if !named => {
+            let mut params = Vec::new();
+            for child in ty.iter_children() {
+                let child = child?;
+                match child.tag()? {
+                    DwTag::FORMAL_PARAMETER if !child.has_attr(DwAt::ARTIFICIAL)? => {
+                        let param = type_of(&child)?;
+                        params.push(c_declare(style, param.as_ref(), String::new())?);
+                    }
+                    DwTag::UNSPECIFIED_PARAMETERS => params.push("...".into()),
+                    _ => (),
+                }
+            }
+            if params.is_empty()
+                && style == Style::C
+                && ty.has_attr(DwAt::PROTOTYPED)?
+                && ty.attr(DwAt::PROTOTYPED)?.get_bool()?
+            {
+                params.push("void".into());
+            }
+            let decl = format!("{}({})", group(decl), params.join(", "));
+            c_declare(style, type_of(ty)?.as_ref(), decl)
+        }
+
+        _ => Ok(join(&c_named(style, ty)?, &decl)),
+    }
+}
+
+/// Get the complete name of a named type, or a placeholder if anonymous.
+fn c_named(style: Style, ty: &Die<'_>) -> Result<String> {
+    let keyword = match ty.tag()? {
+        DwTag::STRUCTURE_TYPE => Some("struct"),
+        DwTag::CLASS_TYPE => Some("class"),
+        DwTag::UNION_TYPE => Some("union"),
+        DwTag::ENUMERATION_TYPE => Some("enum"),
+        _ => None,
+    };
+
+    if !ty.has_attr(DwAt::NAME)? {
+        return Ok(match keyword {
+            Some(keyword) => format!("{} {{...}}", keyword),
+            None => "void".into(),
+        });
+    }
+
+    let name = ty.name()?.to_string_lossy();
+    match style {
+        Style::C => match keyword {
+            Some(keyword) => Ok(format!("{} {}", keyword, name)),
+            None => Ok(name.into_owned()),
+        },
+        _ => qualified(ty, &name),
+    }
+}
+
+/// Prefix a name with its enclosing namespaces and types.
+fn qualified(die: &Die<'_>, name: &str) -> Result<String> {
+    qualify(die.scopes()?.iter().skip(1).rev(), name)
+}
+
+/// Prefix a name with the namespaces and types among `scopes`, outermost first.
+pub(crate) fn qualify<'a, 'dw: 'a, I>(scopes: I, name: &str) -> Result<String>
+where
+    I: IntoIterator<Item = &'a Die<'dw>>,
+{
+    let mut qualified = String::new();
+    for scope in scopes {
+        let anonymous = match scope.tag()? {
+            DwTag::NAMESPACE => "(anonymous namespace)",
+            DwTag::STRUCTURE_TYPE => "(anonymous struct)",
+            DwTag::CLASS_TYPE => "(anonymous class)",
+            DwTag::UNION_TYPE => "(anonymous union)",
+            DwTag::ENUMERATION_TYPE => "(anonymous enum)",
+            _ => continue,
+        };
+        let prefix = match scope.name() {
+            Ok(name) => name.to_string_lossy(),
+            Err(_) => anonymous.into(),
+        };
+        qualified.push_str(&prefix);
+        qualified.push_str("::");
+    }
+    qualified.push_str(name);
+    Ok(qualified)
+}
+
+/// Render the type in Rust syntax.  Most Rust types are named in DWARF,
+/// including pointers and references, but arrays and functions are not.
+fn rust_name(ty: Option<&Die<'_>>) -> Result<String> {
+    let ty = match ty {
+        Some(ty) => ty,
+        None => return Ok("()".into()),
+    };
+
+    if ty.has_attr(DwAt::NAME)? {
+        let name = ty.name()?.to_string_lossy();
+        return match ty.tag()? {
+            DwTag::BASE_TYPE | DwTag::POINTER_TYPE => Ok(name.into_owned()),
+            _ => qualified(ty, &name),
+        };
+    }
+
+    match ty.tag()? {
+        DwTag::POINTER_TYPE => Ok(format!("*const {}", rust_name(type_of(ty)?.as_ref())?)),
+        DwTag::REFERENCE_TYPE => Ok(format!("&{}", rust_name(type_of(ty)?.as_ref())?)),
+        DwTag::ARRAY_TYPE => {
+            let mut name = rust_name(type_of(ty)?.as_ref())?;
+            for dim in array_dimensions(ty)?.iter().rev() {
+                name = match dim.count() {
+                    Some(count) => format!("[{}; {}]", name, count),
+                    None => format!("[{}]", name),
+                };
+            }
+            Ok(name)
+        }
+        DwTag::SUBROUTINE_TYPE => {
+            let mut params = Vec::new();
+            for child in ty.iter_children() {
+                let child = child?;
+                if child.tag()? == DwTag::FORMAL_PARAMETER {
+                    params.push(rust_name(type_of(&child)?.as_ref())?);
+                }
+            }
+            let mut name = format!("fn({})", params.join(", "));
+            if let Some(ret) = type_of(ty)? {
+                name.push_str(" -> ");
+                name.push_str(&rust_name(Some(&ret))?);
+            }
+            Ok(name)
+        }
+        DwTag::CONST_TYPE | DwTag::VOLATILE_TYPE => rust_name(type_of(ty)?.as_ref()),
+        _ => Ok("{unknown}".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::current;
+    use crate::{Die, DwTag, Dwarf, Result};
+
+    fn find_tag<'dw>(dw: &'dw Dwarf<'dw>, tag: DwTag) -> Vec<Die<'dw>> {
+        fn recurse<'dw>(die: &Die<'dw>, tag: DwTag, found: &mut Vec<Die<'dw>>) -> Result<()> {
+            if die.tag()? == tag {
+                found.push(die.clone());
+            }
+            for child in die.iter_children() {
+                recurse(&child?, tag, found)?;
+            }
+            Ok(())
+        }
+
+        let mut found = Vec::new();
+        for cu in dw.compile_units() {
+            recurse(&cu.unwrap().get_die().unwrap(), tag, &mut found).unwrap();
+        }
+        found
+    }
+
+    #[test]
+    fn base_types() {
+        let dw = current();
+        for ty in find_tag(&dw, DwTag::BASE_TYPE) {
+            let name = ty.type_name().unwrap();
+            assert_eq!(name.as_bytes(), ty.name().unwrap().to_bytes());
+            assert_eq!(ty.aggregate_size().unwrap(), ty.byte_size().unwrap() as u64);
+        }
+    }
+
+    #[test]
+    fn arrays() {
+        let dw = current();
+        let arrays = find_tag(&dw, DwTag::ARRAY_TYPE);
+        assert!(!arrays.is_empty());
+        for ty in arrays {
+            let dims = ty.array_dimensions().unwrap();
+            assert!(!dims.is_empty());
+            assert!(dims.iter().all(|dim| dim.lower_bound() == 0));
+
+            let name = ty.type_name().unwrap();
+            assert!(name.starts_with('['), "{}", name);
+        }
+    }
+
+    #[test]
+    fn peel_typedef() {
+        let dw = current();
+        for ty in find_tag(&dw, DwTag::TYPEDEF) {
+            if let Ok(peeled) = ty.peel_type() {
+                assert_ne!(peeled.tag().unwrap(), DwTag::TYPEDEF);
+            }
+        }
+    }
+}
@@ -1,9 +1,15 @@
 use crate::ffi;
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
 use std::ptr;
 
 use crate::ffi::Dwarf_Off;
 
+use super::Abbrevs;
 use super::Die;
+use super::DieTree;
+use super::DwAt;
 use super::Dwarf;
 use super::Result;
 
@@ -30,40 +36,45 @@ impl<'dw> Iterator for CompileUnits<'dw> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        if self.finished {
-            return None;
-        }
+        while !self.finished {
+            let offset = self.offset;
+            let mut header_size = 0;
 
-        let offset = self.offset;
-        let mut header_size = 0;
+            let rc = ffi!(dwarf_next_unit(
+                self.dwarf.as_ptr(),
+                offset,
+                &mut self.offset,
+                &mut header_size,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut()
+            ));
 
-        let rc = ffi!(dwarf_next_unit(
-            self.dwarf.as_ptr(),
-            offset,
-            &mut self.offset,
-            &mut header_size,
-            ptr::null_mut(),
-            ptr::null_mut(),
-            ptr::null_mut(),
-            ptr::null_mut(),
-            ptr::null_mut(),
-            ptr::null_mut()
-        ));
+            match rc {
+                Ok(0) => {
+                    let die_offset = offset + header_size as Dwarf_Off;
+                    let unit = CompileUnit::new(self.dwarf, die_offset);
 
-        match rc {
-            Ok(0) => {
-                let die_offset = offset + header_size as Dwarf_Off;
-                Some(Ok(CompileUnit::new(self.dwarf, die_offset)))
-            }
-            Ok(_) => {
-                self.finished = true;
-                None
-            }
-            Err(e) => {
-                self.finished = true;
-                Some(Err(e))
+                    // Partial and type units are only reachable via `units()`.
+                    match unit.header().map(|header| header.unit_type()) {
+                        Ok(UnitType::Compile | UnitType::Skeleton | UnitType::SplitCompile) => {
+                            return Some(Ok(unit))
+                        }
+                        Ok(_) => continue,
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                Ok(_) => self.finished = true,
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
             }
         }
+        None
     }
 }
 
@@ -154,6 +165,59 @@ impl<'dw> CompileUnit<'dw> {
     pub fn get_die(&self) -> Result<Die<'dw>> {
         Die::from_offset(self.dwarf, self.die_offset)
     }
+
+    /// Get the unit DIE of the split unit for a skeleton unit from
+    /// `-gsplit-dwarf`, if its `.dwo` file can be found.
+    ///
+    /// The split DIE belongs to the `.dwo` file, so its offsets are not
+    /// comparable with those of the skeleton.
+    #[inline]
+    pub fn split_die(&self) -> Result<Option<Die<'dw>>> {
+        self.get_die()?.split_die()
+    }
+
+    /// Iterate over the abbreviation table of this unit.
+    #[inline]
+    pub fn abbrevs(&self) -> Result<Abbrevs<'dw>> {
+        Ok(self.get_die()?.abbrevs())
+    }
+
+    /// Walk the unit DIE and all of its descendants, depth first.
+    #[inline]
+    pub fn tree(&self) -> Result<DieTree<'dw>> {
+        Ok(self.get_die()?.tree())
+    }
+
+    /// Get the expected path of the `.dwo` file for a skeleton unit, from
+    /// its `DW_AT_dwo_name` and `DW_AT_comp_dir`.
+    ///
+    /// libdw searches there automatically.  Packaged `.dwp` files are only
+    /// searched with libdw 0.191 or later.
+    pub fn dwo_path(&self) -> Result<Option<PathBuf>> {
+        let die = self.get_die()?;
+        let name = if die.has_attr(DwAt::DWO_NAME)? {
+            die.attr(DwAt::DWO_NAME)?.get_string()?
+        } else if die.has_attr(DwAt::GNU_DWO_NAME)? {
+            die.attr(DwAt::GNU_DWO_NAME)?.get_string()?
+        } else {
+            return Ok(None);
+        };
+
+        let name = Path::new(OsStr::from_bytes(name.to_bytes()));
+        if die.has_attr(DwAt::COMP_DIR)? {
+            let dir = die.attr(DwAt::COMP_DIR)?.get_string()?;
+            Ok(Some(
+                Path::new(OsStr::from_bytes(dir.to_bytes())).join(name),
+            ))
+        } else {
+            Ok(Some(name.to_owned()))
+        }
+    }
+
+    #[inline]
+    pub fn header(&self) -> Result<UnitHeader> {
+        UnitHeader::new(&self.get_die()?)
+    }
 }
 
 #[derive(Debug)]
@@ -194,4 +258,317 @@ impl<'dw> TypeUnit<'dw> {
     pub fn signature(&self) -> u64 {
         self.signature
     }
+
+    #[inline]
+    pub fn header(&self) -> Result<UnitHeader> {
+        UnitHeader::new(&self.get_die()?)
+    }
+}
+
+/// An iterator over every unit, including partial and type units.
+#[derive(Debug)]
+pub struct Units<'dw> {
+    dwarf: &'dw Dwarf<'dw>,
+    cu: *mut ffi::Dwarf_CU,
+    finished: bool,
+}
+
+impl<'dw> Units<'dw> {
+    #[inline]
+    pub(crate) fn new(dwarf: &'dw Dwarf<'dw>) -> Units<'dw> {
+        Units {
+            dwarf,
+            cu: ptr::null_mut(),
+            finished: false,
+        }
+    }
+}
+
+impl<'dw> Iterator for Units<'dw> {
+    type Item = Result<Unit<'dw>>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let die = Die::default();
+        let subdie = Die::default();
+        let rc = ffi!(dwarf_get_units(
+            self.dwarf.as_ptr(),
+            self.cu,
+            &mut self.cu,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            die.as_ptr(),
+            subdie.as_ptr()
+        ));
+
+        match rc {
+            Ok(0) => Some(Unit::new(die, subdie)),
+            Ok(_) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// A unit of any type, from `Dwarf::units`.
+#[derive(Debug)]
+pub struct Unit<'dw> {
+    header: UnitHeader,
+    die: Die<'dw>,
+    subdie: Die<'dw>,
+}
+
+impl<'dw> Unit<'dw> {
+    #[inline]
+    fn new(die: Die<'dw>, subdie: Die<'dw>) -> Result<Unit<'dw>> {
+        let header = UnitHeader::new(&die)?;
+        Ok(Unit {
+            header,
+            die,
+            subdie,
+        })
+    }
+
+    #[inline]
+    pub fn header(&self) -> &UnitHeader {
+        &self.header
+    }
+
+    #[inline]
+    pub fn unit_type(&self) -> UnitType {
+        self.header.unit_type
+    }
+
+    /// Get the unit DIE.
+    #[inline]
+    pub fn get_die(&self) -> Die<'dw> {
+        self.die.clone()
+    }
+
+    /// Get the secondary DIE: the type DIE of a type unit, or the unit DIE
+    /// of the split unit for a skeleton, if found.
+    #[inline]
+    pub fn get_subdie(&self) -> Option<Die<'dw>> {
+        if unsafe { (*self.subdie.as_ptr()).addr.is_null() } {
+            None
+        } else {
+            Some(self.subdie.clone())
+        }
+    }
+}
+
+/// The kind of unit, from `DW_UT_*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitType {
+    Compile,
+    Type,
+    Partial,
+    Skeleton,
+    SplitCompile,
+    SplitType,
+    #[doc(hidden)] // non-exhaustive
+    Unknown(u8),
+}
+
+impl From<u8> for UnitType {
+    fn from(unit_type: u8) -> UnitType {
+        match u32::from(unit_type) {
+            ffi::DW_UT_compile => UnitType::Compile,
+            ffi::DW_UT_type => UnitType::Type,
+            ffi::DW_UT_partial => UnitType::Partial,
+            ffi::DW_UT_skeleton => UnitType::Skeleton,
+            ffi::DW_UT_split_compile => UnitType::SplitCompile,
+            ffi::DW_UT_split_type => UnitType::SplitType,
+            _ => UnitType::Unknown(unit_type),
+        }
+    }
+}
+
+/// The header fields of a unit.
+///
+/// Units older than DWARF 5 don't record their type, so libdw infers it
+/// from the section and the unit DIE's tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnitHeader {
+    offset: Dwarf_Off,
+    version: u16,
+    unit_type: UnitType,
+    address_size: u8,
+    offset_size: u8,
+    abbrev_offset: Dwarf_Off,
+    unit_id: u64,
+}
+
+impl UnitHeader {
+    fn new(die: &Die<'_>) -> Result<UnitHeader> {
+        let cu = unsafe { (*die.as_ptr()).cu };
+
+        let mut version = 0;
+        let mut unit_type = 0;
+        let mut unit_id = 0;
+        let mut address_size = 0;
+        let mut offset_size = 0;
+        ffi!(dwarf_cu_info(
+            cu,
+            &mut version,
+            &mut unit_type,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &mut unit_id,
+            &mut address_size,
+            &mut offset_size
+        ))?;
+
+        let cudie = Die::default();
+        let mut abbrev_offset = 0;
+        ffi!(dwarf_cu_die(
+            cu,
+            cudie.as_ptr(),
+            ptr::null_mut(),
+            &mut abbrev_offset,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut()
+        ))?;
+
+        Ok(UnitHeader {
+            offset: cudie.offset() - cudie.unit_offset(),
+            version,
+            unit_type: unit_type.into(),
+            address_size,
+            offset_size,
+            abbrev_offset,
+            unit_id,
+        })
+    }
+
+    /// Get the offset of the unit header in its section.
+    #[inline]
+    pub fn offset(&self) -> Dwarf_Off {
+        self.offset
+    }
+
+    #[inline]
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    #[inline]
+    pub fn unit_type(&self) -> UnitType {
+        self.unit_type
+    }
+
+    #[inline]
+    pub fn address_size(&self) -> u8 {
+        self.address_size
+    }
+
+    /// Get the size of section offsets: 4 for 32-bit DWARF, or 8 for 64-bit.
+    #[inline]
+    pub fn offset_size(&self) -> u8 {
+        self.offset_size
+    }
+
+    #[inline]
+    pub fn abbrev_offset(&self) -> Dwarf_Off {
+        self.abbrev_offset
+    }
+
+    /// Get the DWO id of a skeleton or split unit, or the signature of a
+    /// type unit.
+    #[inline]
+    pub fn unit_id(&self) -> u64 {
+        self.unit_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DwTag, Dwarf, UnitType};
+
+    #[test]
+    fn unit_headers() {
+        let exe = std::env::current_exe().unwrap();
+        let dw = Dwarf::open(exe).unwrap();
+
+        let mut compile_units = dw.compile_units();
+        for unit in dw.units() {
+            let unit = unit.unwrap();
+            let header = unit.header();
+            assert!(header.version() >= 2);
+            assert!(header.offset_size() == 4 || header.offset_size() == 8);
+            assert_eq!(
+                unit.get_die().offset(),
+                header.offset() + unit.get_die().unit_offset()
+            );
+
+            if header.unit_type() == UnitType::Compile {
+                let cu = compile_units.next().unwrap().unwrap();
+                assert_eq!(cu.header().unwrap(), *header);
+            }
+        }
+        assert!(compile_units.next().is_none());
+    }
+
+    #[test]
+    fn not_split() {
+        let exe = std::env::current_exe().unwrap();
+        let dw = Dwarf::open(exe).unwrap();
+        for cu in dw.compile_units() {
+            let cu = cu.unwrap();
+            assert_eq!(cu.header().unwrap().unit_type(), UnitType::Compile);
+            assert!(cu.split_die().unwrap().is_none());
+            assert!(cu.dwo_path().unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn split() {
+        let dir = std::env::temp_dir().join(format!("libdw-split-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("split.c"), "int answer(void) { return 42; }\n").unwrap();
+        // Link the object, since libdw doesn't apply relocations itself.
+        let cc = |args: &[&str]| {
+            let status = std::process::Command::new("cc")
+                .args(args)
+                .current_dir(&dir)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        cc(&["-g", "-gdwarf-5", "-gsplit-dwarf", "-fPIC", "-c", "split.c"]);
+        cc(&["-shared", "split.o", "-o", "split.so"]);
+
+        let dw = Dwarf::open(dir.join("split.so")).unwrap();
+        let mut compile_units = dw.compile_units();
+        let cu = compile_units.next().unwrap().unwrap();
+        assert!(compile_units.next().is_none());
+        assert_eq!(cu.header().unwrap().unit_type(), UnitType::Skeleton);
+        assert_eq!(cu.dwo_path().unwrap(), Some(dir.join("split.dwo")));
+
+        let skeleton = cu.get_die().unwrap();
+        assert_eq!(skeleton.tag().unwrap(), DwTag::SKELETON_UNIT);
+        assert_eq!(skeleton.offset(), cu.get_die().unwrap().offset());
+        assert!(skeleton.child().unwrap().is_none());
+
+        let split = cu.split_die().unwrap().unwrap();
+        assert_eq!(split.tag().unwrap(), DwTag::COMPILE_UNIT);
+        let names: Vec<_> = split
+            .iter_children()
+            .map(|child| child.unwrap().name().unwrap().to_owned())
+            .collect();
+        assert!(names.iter().any(|name| name.to_bytes() == b"answer"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
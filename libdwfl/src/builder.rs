@@ -0,0 +1,432 @@
+use crate::ffi;
+
+use std::cell::{Cell, RefCell};
+use std::ffi::{CStr, CString, OsStr};
+use std::io::Read;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+use libelf::raw as elf;
+use libelf::Elf;
+
+use super::callback::catch_panic;
+use super::Dwfl;
+use super::Module;
+use super::Result;
+
+type FindElf<'a> = dyn FnMut(Module<'_>, &CStr, u64) -> Option<PathBuf> + 'a;
+type FindDebuginfo<'a> = dyn FnMut(Module<'_>, Option<&CStr>, u32) -> Option<PathBuf> + 'a;
+type SectionAddress<'a> = dyn FnMut(Module<'_>, &CStr, u32) -> Option<u64> + 'a;
+
+/// A builder for a `Dwfl` with a custom debuginfo path or callbacks.
+///
+/// Each callback may return `None` to fall back to the standard search.
+///
+/// # Examples
+///
+/// ```
+/// let exe = std::env::current_exe().unwrap();
+/// let dwfl = libdwfl::Dwfl::builder()
+///     .debuginfo_path("/srv/debuginfo")
+///     .find_debuginfo(|module, _debuglink, _crc| {
+///         let id = module.build_id().ok()??;
+///         let hex: String = id.iter().map(|b| format!("{:02x}", b)).collect();
+///         Some(format!("/srv/debuginfo/{}", hex).into())
+///     })
+///     .open(exe)
+///     .unwrap();
+/// ```
+#[derive(Default)]
+pub struct DwflBuilder<'a> {
+    debuginfo_path: Option<CString>,
+    find_elf: Option<Box<FindElf<'a>>>,
+    find_debuginfo: Option<Box<FindDebuginfo<'a>>>,
+    section_address: Option<Box<SectionAddress<'a>>>,
+}
+
+impl<'a> DwflBuilder<'a> {
+    #[inline]
+    pub fn new() -> Self {
+        DwflBuilder::default()
+    }
+
+    /// Set the search path for separate debuginfo files, in the format of the
+    /// `--debuginfo-path` option of elfutils tools.
+    pub fn debuginfo_path<S: AsRef<OsStr>>(mut self, path: S) -> Self {
+        self.debuginfo_path = Some(CString::new(path.as_ref().as_bytes()).unwrap());
+        self
+    }
+
+    /// Find the main ELF file of a module, given its name and base address.
+    pub fn find_elf<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(Module<'_>, &CStr, u64) -> Option<PathBuf> + 'a,
+    {
+        self.find_elf = Some(Box::new(f));
+        self
+    }
+
+    /// Find the debuginfo file of a module, given the file name and CRC from
+    /// its `.gnu_debuglink` section, if any.
+    pub fn find_debuginfo<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(Module<'_>, Option<&CStr>, u32) -> Option<PathBuf> + 'a,
+    {
+        self.find_debuginfo = Some(Box::new(f));
+        self
+    }
+
+    /// Choose the address of a section in a relocatable module, given its name
+    /// and index.  An address of `u64::MAX` means the section isn't loaded.
+    pub fn section_address<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(Module<'_>, &CStr, u32) -> Option<u64> + 'a,
+    {
+        self.section_address = Some(Box::new(f));
+        self
+    }
+
+    /// Open a `Dwfl` from a path, like `Dwfl::open`.
+    pub fn open<P: AsRef<Path>>(self, path: P) -> Result<Dwfl<'a>> {
+        Callbacks::new::<false>(self).report(|dwfl| dwfl.report_offline(path.as_ref()))
+    }
+
+    /// Open a `Dwfl` for the modules of a live process, like `Dwfl::from_pid`.
+    pub fn from_pid(self, pid: libc::pid_t) -> Result<Dwfl<'a>> {
+        Callbacks::new::<true>(self).report(|dwfl| dwfl.report_pid(pid))
+    }
+
+    /// Open a `Dwfl` for the modules listed in the text of a `/proc/PID/maps`
+    /// file, like `Dwfl::from_proc_maps`.
+    pub fn from_proc_maps<R: Read>(self, reader: R) -> Result<Dwfl<'a>> {
+        Callbacks::new::<true>(self).report(|dwfl| dwfl.report_proc_maps(reader))
+    }
+
+    /// Open a `Dwfl` for the modules mapped in a core file, like
+    /// `Dwfl::from_core`.
+    pub fn from_core(self, core: &'a Elf<'_>, executable: Option<&Path>) -> Result<Dwfl<'a>> {
+        Callbacks::new::<false>(self).report(|dwfl| dwfl.report_core(core, executable))
+    }
+}
+
+/// The callbacks of a `Dwfl` made by `DwflBuilder`.
+///
+/// libdwfl doesn't give its callbacks any context of their own, so these are
+/// kept in the userdata of each module, which is otherwise unused.
+pub(crate) struct Callbacks<'a> {
+    table: ffi::Dwfl_Callbacks,
+    // The table points to this pointer, which points into this string.
+    debuginfo_path: *mut libc::c_char,
+    _debuginfo_path: Option<CString>,
+    find_elf: Option<RefCell<Box<FindElf<'a>>>>,
+    find_debuginfo: Option<RefCell<Box<FindDebuginfo<'a>>>>,
+    section_address: Option<RefCell<Box<SectionAddress<'a>>>>,
+}
+
+impl<'a> Callbacks<'a> {
+    /// Make the callbacks, falling back to the standard callbacks for a live
+    /// process if `PROC`, or else for files.
+    fn new<const PROC: bool>(builder: DwflBuilder<'a>) -> Box<Self> {
+        let table = ffi::Dwfl_Callbacks {
+            find_elf: Some(match builder.find_elf {
+                Some(_) => find_elf::<PROC>,
+                None if PROC => ffi::dwfl_linux_proc_find_elf,
+                None => ffi::dwfl_build_id_find_elf,
+            }),
+            find_debuginfo: Some(match builder.find_debuginfo {
+                Some(_) => find_debuginfo,
+                None => ffi::dwfl_standard_find_debuginfo,
+            }),
+            section_address: match builder.section_address {
+                Some(_) => Some(section_address::<PROC>),
+                None if PROC => None,
+                None => Some(ffi::dwfl_offline_section_address),
+            },
+            debuginfo_path: ptr::null_mut(),
+        };
+
+        let mut callbacks = Box::new(Callbacks {
+            table,
+            debuginfo_path: builder
+                .debuginfo_path
+                .as_ref()
+                .map_or(ptr::null_mut(), |path| path.as_ptr() as *mut _),
+            _debuginfo_path: builder.debuginfo_path,
+            find_elf: builder.find_elf.map(RefCell::new),
+            find_debuginfo: builder.find_debuginfo.map(RefCell::new),
+            section_address: builder.section_address.map(RefCell::new),
+        });
+        callbacks.table.debuginfo_path = &mut callbacks.debuginfo_path;
+        callbacks
+    }
+
+    #[inline]
+    pub(crate) fn table(&self) -> *const ffi::Dwfl_Callbacks {
+        &self.table
+    }
+
+    /// Begin a `Dwfl` with these callbacks, and report its modules.
+    fn report<F>(self: Box<Self>, report: F) -> Result<Dwfl<'a>>
+    where
+        F: FnOnce(&Dwfl<'a>) -> Result<()>,
+    {
+        let callbacks = &*self as *const Self as *mut libc::c_void;
+        let dwfl = Dwfl::with_callbacks(self)?;
+        {
+            // Callbacks made while reporting find these as the current ones.
+            let _reporting = Reporting::enter(callbacks);
+            report(&dwfl)?;
+        }
+
+        // Later callbacks find these in the userdata of their module.
+        for module in dwfl.modules() {
+            let userdata = userdata(module?);
+            unsafe {
+                if (*userdata).is_null() {
+                    *userdata = callbacks;
+                }
+            }
+        }
+        Ok(dwfl)
+    }
+
+    /// Get the callbacks of `module`, given the pointer to its userdata.
+    unsafe fn of_module<'x>(userdata: *mut *mut libc::c_void) -> Option<&'x Callbacks<'x>> {
+        if userdata.is_null() {
+            return None;
+        }
+        if (*userdata).is_null() {
+            // The module is still being reported.
+            *userdata = REPORTING.with(Cell::get);
+        }
+        (*userdata as *const Callbacks<'x>).as_ref()
+    }
+}
+
+thread_local! {
+    // The callbacks of the `Dwfl` reporting modules on this thread, if any.
+    // A `Dwfl` is not `Send`, so its callbacks run on this thread.
+    static REPORTING: Cell<*mut libc::c_void> = const { Cell::new(ptr::null_mut()) };
+}
+
+/// Marks the callbacks of a `Dwfl` as current while it reports modules.
+struct Reporting {
+    previous: *mut libc::c_void,
+}
+
+impl Reporting {
+    fn enter(callbacks: *mut libc::c_void) -> Reporting {
+        Reporting {
+            previous: REPORTING.with(|reporting| reporting.replace(callbacks)),
+        }
+    }
+}
+
+impl Drop for Reporting {
+    fn drop(&mut self) {
+        REPORTING.with(|reporting| reporting.set(self.previous));
+    }
+}
+
+/// Get the pointer to the userdata of `module`.
+fn userdata(module: Module<'_>) -> *mut *mut libc::c_void {
+    let mut userdata = ptr::null_mut();
+    raw_ffi!(dwfl_module_info(
+        module.as_ptr(),
+        &mut userdata,
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut()
+    ));
+    userdata
+}
+
+/// Callbacks owned by a `Dwfl`, as a trait object so their lifetime stays
+/// covariant.
+pub(crate) trait OwnedCallbacks {}
+
+impl<'a> OwnedCallbacks for Callbacks<'a> {}
+
+/// Call a Rust callback, or return `None` to use the standard callback if there
+/// is none, it is already running in a nested call, or it panicked.
+fn call<C: ?Sized, T, F>(callback: Option<&RefCell<Box<C>>>, f: F) -> Option<T>
+where
+    F: FnOnce(&mut C) -> Option<T>,
+{
+    let mut callback = callback?.try_borrow_mut().ok()?;
+    catch_panic(None, || f(&mut **callback))
+}
+
+/// Open a file found by a Rust callback, returning its descriptor and name.
+unsafe fn open_file(path: &Path, file_name: *mut *mut libc::c_char) -> libc::c_int {
+    let path = match CString::new(path.as_os_str().as_bytes()) {
+        Ok(path) => path,
+        Err(_) => return -1,
+    };
+    let fd = libc::open(path.as_ptr(), libc::O_RDONLY | libc::O_CLOEXEC);
+    if fd >= 0 {
+        // libdwfl will free the name.
+        *file_name = libc::strdup(path.as_ptr());
+    }
+    fd
+}
+
+unsafe extern "C" fn find_elf<const PROC: bool>(
+    module: *mut ffi::Dwfl_Module,
+    userdata: *mut *mut libc::c_void,
+    modname: *const libc::c_char,
+    base: ffi::Dwarf_Addr,
+    file_name: *mut *mut libc::c_char,
+    elfp: *mut *mut elf::Elf,
+) -> libc::c_int {
+    let path = Callbacks::of_module(userdata).and_then(|callbacks| {
+        call(callbacks.find_elf.as_ref(), |f| {
+            f(Module::from_raw(module), CStr::from_ptr(modname), base)
+        })
+    });
+    match path {
+        Some(path) => open_file(&path, file_name),
+        None if PROC => {
+            ffi::dwfl_linux_proc_find_elf(module, userdata, modname, base, file_name, elfp)
+        }
+        None => ffi::dwfl_build_id_find_elf(module, userdata, modname, base, file_name, elfp),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe extern "C" fn find_debuginfo(
+    module: *mut ffi::Dwfl_Module,
+    userdata: *mut *mut libc::c_void,
+    modname: *const libc::c_char,
+    base: ffi::Dwarf_Addr,
+    file_name: *const libc::c_char,
+    debuglink_file: *const libc::c_char,
+    debuglink_crc: elf::GElf_Word,
+    debuginfo_file_name: *mut *mut libc::c_char,
+) -> libc::c_int {
+    let path = Callbacks::of_module(userdata).and_then(|callbacks| {
+        call(callbacks.find_debuginfo.as_ref(), |f| {
+            let debuglink = if debuglink_file.is_null() {
+                None
+            } else {
+                Some(CStr::from_ptr(debuglink_file))
+            };
+            f(Module::from_raw(module), debuglink, debuglink_crc)
+        })
+    });
+    match path {
+        Some(path) => open_file(&path, debuginfo_file_name),
+        None => ffi::dwfl_standard_find_debuginfo(
+            module,
+            userdata,
+            modname,
+            base,
+            file_name,
+            debuglink_file,
+            debuglink_crc,
+            debuginfo_file_name,
+        ),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe extern "C" fn section_address<const PROC: bool>(
+    module: *mut ffi::Dwfl_Module,
+    userdata: *mut *mut libc::c_void,
+    modname: *const libc::c_char,
+    base: ffi::Dwarf_Addr,
+    secname: *const libc::c_char,
+    shndx: elf::GElf_Word,
+    shdr: *const elf::GElf_Shdr,
+    addr: *mut ffi::Dwarf_Addr,
+) -> libc::c_int {
+    let address = Callbacks::of_module(userdata).and_then(|callbacks| {
+        call(callbacks.section_address.as_ref(), |f| {
+            f(Module::from_raw(module), CStr::from_ptr(secname), shndx)
+        })
+    });
+    match address {
+        Some(address) => {
+            *addr = address;
+            0
+        }
+        None if PROC => -1,
+        None => ffi::dwfl_offline_section_address(
+            module, userdata, modname, base, secname, shndx, shdr, addr,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Dwfl;
+    use std::path::PathBuf;
+    use std::{env, panic, process};
+
+    #[test]
+    fn find_elf() {
+        let exe = env::current_exe().unwrap();
+        let mut names = Vec::new();
+        {
+            let dwfl = Dwfl::builder()
+                .find_elf(|_, name, _| {
+                    names.push(name.to_owned());
+                    // Leave special modules like the vDSO to the standard search.
+                    let name = name.to_str().ok()?;
+                    name.starts_with('/').then(|| PathBuf::from(name))
+                })
+                .from_pid(process::id() as libc::pid_t)
+                .unwrap();
+            for module in dwfl.modules() {
+                let module = module.unwrap();
+                module.elf().unwrap();
+                if module.main_file() == Some(&exe) {
+                    module.dwarf().unwrap();
+                }
+            }
+        }
+        assert!(names
+            .iter()
+            .any(|name| name.to_bytes() == exe.as_os_str().as_encoded_bytes()));
+
+        let dwfl = Dwfl::builder()
+            .find_elf(|_, _, _| panic!("find_elf"))
+            .from_pid(process::id() as libc::pid_t)
+            .unwrap();
+        let module = dwfl.modules().next().unwrap().unwrap();
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| module.elf()));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn separate_callbacks() {
+        let pid = process::id() as libc::pid_t;
+        let (mut first, mut second) = (0, 0);
+        {
+            let dwfl1 = Dwfl::builder()
+                .find_elf(|_, _, _| {
+                    first += 1;
+                    None
+                })
+                .from_pid(pid)
+                .unwrap();
+            let dwfl2 = Dwfl::builder()
+                .find_elf(|_, _, _| {
+                    second += 1;
+                    None
+                })
+                .from_pid(pid)
+                .unwrap();
+
+            // Each `Dwfl` calls its own closure, even while another exists.
+            dwfl1.modules().next().unwrap().unwrap().elf().unwrap();
+            drop(dwfl1);
+            dwfl2.modules().next().unwrap().unwrap().elf().unwrap();
+        }
+        assert_eq!((first, second), (1, 1));
+    }
+}
@@ -0,0 +1,103 @@
+use crate::ffi;
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::panic;
+
+use super::Result;
+
+#[inline]
+pub(crate) fn dwarf_cb_map<T>(cont: Result<bool>, result: &mut Result<T>) -> libc::c_uint {
+    match cont {
+        Ok(true) => ffi::DWARF_CB_OK,
+        Ok(false) => ffi::DWARF_CB_ABORT,
+        Err(e) => {
+            *result = Err(e);
+            ffi::DWARF_CB_ABORT
+        }
+    }
+}
+
+/// Catch panics in Rust callbacks, so they can be resumed after libdwfl returns.
+pub(crate) struct CallbackGuard {
+    payload: Option<Box<dyn Any + Send>>,
+}
+
+impl CallbackGuard {
+    #[inline]
+    pub(crate) fn new() -> CallbackGuard {
+        CallbackGuard { payload: None }
+    }
+
+    /// Call `f`, or return `abort` if it panics or a previous call panicked.
+    pub(crate) fn call<T, F>(&mut self, abort: T, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        if self.payload.is_some() {
+            // We already panicked!
+            return abort;
+        }
+
+        // Asserted safe because we'll rethrow after the ffi returns,
+        // so no one can see any possibly inconsistent state.
+        let call = panic::AssertUnwindSafe(f);
+
+        match panic::catch_unwind(call) {
+            Ok(rc) => rc,
+            Err(e) => {
+                self.payload = Some(e);
+                abort
+            }
+        }
+    }
+}
+
+impl Drop for CallbackGuard {
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(payload) = self.payload.take() {
+            panic::resume_unwind(payload);
+        }
+    }
+}
+
+thread_local! {
+    static PANIC: RefCell<Option<Box<dyn Any + Send>>> = const { RefCell::new(None) };
+}
+
+/// Call `f` from a callback that libdwfl keeps beyond a single call, or
+/// return `abort` if it panics or a previous call panicked.
+///
+/// The panic is saved and resumed when the libdwfl call returns to Rust.
+pub(crate) fn catch_panic<T, F>(abort: T, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    if PANIC.with(|p| p.borrow().is_some()) {
+        return abort;
+    }
+
+    let call = panic::AssertUnwindSafe(f);
+    match panic::catch_unwind(call) {
+        Ok(rc) => rc,
+        Err(e) => {
+            PANIC.with(|p| *p.borrow_mut() = Some(e));
+            abort
+        }
+    }
+}
+
+/// Resume a panic saved by `catch_panic`.
+#[inline]
+pub(crate) fn resume_panic() {
+    if let Some(payload) = PANIC.with(|p| p.borrow_mut().take()) {
+        panic::resume_unwind(payload);
+    }
+}
+
+/// Discard a panic saved by `catch_panic`, where it can't be resumed.
+#[inline]
+pub(crate) fn discard_panic() {
+    PANIC.with(|p| p.borrow_mut().take());
+}
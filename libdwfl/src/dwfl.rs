@@ -1,13 +1,26 @@
 use crate::ffi;
 
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
+use std::io::{self, Read};
+use std::marker::PhantomData;
 use std::os::unix::ffi::OsStrExt;
 use std::path::Path;
 use std::ptr;
 
 use libdw::Dwarf;
+use libelf::Elf;
 
+use super::builder::{Callbacks, OwnedCallbacks};
+use super::callback::{discard_panic, dwarf_cb_map, CallbackGuard};
+use super::thread::{frame_callback, CallbackState};
+use super::DwflBuilder;
+use super::Module;
+use super::Modules;
 use super::Result;
+use super::SourceLine;
+use super::Symbol;
+use super::{Error, Frame, Thread, ThreadCallbacks};
+use crate::error::IntoResult;
 
 const STANDARD_CALLBACKS: &'static ffi::Dwfl_Callbacks = &ffi::Dwfl_Callbacks {
     find_elf: Some(ffi::dwfl_build_id_find_elf),
@@ -16,14 +29,40 @@ const STANDARD_CALLBACKS: &'static ffi::Dwfl_Callbacks = &ffi::Dwfl_Callbacks {
     debuginfo_path: 0 as *mut _, //ptr::null_mut(),
 };
 
-pub struct Dwfl {
+const PROC_CALLBACKS: &ffi::Dwfl_Callbacks = &ffi::Dwfl_Callbacks {
+    find_elf: Some(ffi::dwfl_linux_proc_find_elf),
+    find_debuginfo: Some(ffi::dwfl_standard_find_debuginfo),
+    section_address: None,
+    debuginfo_path: ptr::null_mut(),
+};
+
+pub struct Dwfl<'elf> {
     inner: *mut ffi::Dwfl,
+    callbacks: Option<Box<dyn OwnedCallbacks + 'elf>>,
+    phantom: PhantomData<&'elf Elf<'elf>>,
 }
 
-impl Dwfl {
+impl<'elf> Dwfl<'elf> {
     #[inline]
     fn new(dwfl: *mut ffi::Dwfl) -> Self {
-        Dwfl { inner: dwfl }
+        Dwfl {
+            inner: dwfl,
+            callbacks: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Begin a `Dwfl` with custom callbacks, which it will own.
+    pub(crate) fn with_callbacks(callbacks: Box<Callbacks<'elf>>) -> Result<Self> {
+        let mut dwfl = Dwfl::new(ffi!(dwfl_begin(callbacks.table()))?);
+        dwfl.callbacks = Some(callbacks);
+        Ok(dwfl)
+    }
+
+    /// Start building a `Dwfl` with a custom debuginfo path or callbacks.
+    #[inline]
+    pub fn builder() -> DwflBuilder<'elf> {
+        DwflBuilder::new()
     }
 
     /// Open a `Dwfl` from a path.
@@ -34,20 +73,115 @@ impl Dwfl {
     /// let exe = std::env::current_exe().unwrap();
     /// let dw = libdwfl::Dwfl::open(exe).unwrap();
     /// ```
-    pub fn open<P: AsRef<Path>>(path: P) -> Result<Dwfl> {
-        let name = CString::new(path.as_ref().as_os_str().as_bytes()).unwrap();
-
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Dwfl<'static>> {
         let dwfl = Dwfl::new(ffi!(dwfl_begin(STANDARD_CALLBACKS))?);
+        dwfl.report_offline(path.as_ref())?;
+        Ok(dwfl)
+    }
+
+    pub(crate) fn report_offline(&self, path: &Path) -> Result<()> {
+        let name = CString::new(path.as_os_str().as_bytes()).unwrap();
         ffi!(dwfl_report_offline(
-            dwfl.as_ptr(),
+            self.as_ptr(),
             name.as_ptr(),
             name.as_ptr(),
             -1
         ))?;
-        ffi!(dwfl_report_end(dwfl.as_ptr(), None, ptr::null_mut()))?;
+        self.report_end()
+    }
+
+    /// Open a `Dwfl` for the modules of a live process.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pid = std::process::id() as libc::pid_t;
+    /// let dwfl = libdwfl::Dwfl::from_pid(pid).unwrap();
+    /// assert!(dwfl.modules().count() > 1);
+    /// ```
+    pub fn from_pid(pid: libc::pid_t) -> Result<Dwfl<'static>> {
+        let dwfl = Dwfl::new(ffi!(dwfl_begin(PROC_CALLBACKS))?);
+        dwfl.report_pid(pid)?;
+        Ok(dwfl)
+    }
+
+    pub(crate) fn report_pid(&self, pid: libc::pid_t) -> Result<()> {
+        proc_result(raw_ffi!(dwfl_linux_proc_report(self.as_ptr(), pid)))?;
+        self.report_end()
+    }
+
+    /// Open a `Dwfl` for the modules listed in the text of a `/proc/PID/maps`
+    /// file, which may have been saved from a process that is no longer running.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let maps = std::fs::File::open("/proc/self/maps").unwrap();
+    /// let dwfl = libdwfl::Dwfl::from_proc_maps(maps).unwrap();
+    /// assert!(dwfl.modules().count() > 1);
+    /// ```
+    pub fn from_proc_maps<R: Read>(reader: R) -> Result<Dwfl<'static>> {
+        let dwfl = Dwfl::new(ffi!(dwfl_begin(PROC_CALLBACKS))?);
+        dwfl.report_proc_maps(reader)?;
+        Ok(dwfl)
+    }
+
+    pub(crate) fn report_proc_maps<R: Read>(&self, mut reader: R) -> Result<()> {
+        let mut maps = Vec::new();
+        reader.read_to_end(&mut maps)?;
+
+        if !maps.is_empty() {
+            let file =
+                unsafe { libc::fmemopen(maps.as_mut_ptr().cast(), maps.len(), c"r".as_ptr()) };
+            if file.is_null() {
+                return Err(io::Error::last_os_error().into());
+            }
+            let rc = raw_ffi!(dwfl_linux_proc_maps_report(self.as_ptr(), file));
+            unsafe { libc::fclose(file) };
+            proc_result(rc)?;
+        }
+        self.report_end()
+    }
+
+    /// Open a `Dwfl` for the modules mapped in a core file.
+    ///
+    /// Modules are found from the core's `NT_FILE` and build-id notes, and the
+    /// main executable may be given explicitly if its path was not recorded.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let core = libelf::Elf::open("core").unwrap();
+    /// let dwfl = libdwfl::Dwfl::from_core(&core, None).unwrap();
+    /// for module in dwfl.modules() {
+    ///     println!("{:?}", module.unwrap());
+    /// }
+    /// ```
+    pub fn from_core(core: &'elf Elf<'_>, executable: Option<&Path>) -> Result<Dwfl<'elf>> {
+        let dwfl = Dwfl::new(ffi!(dwfl_begin(STANDARD_CALLBACKS))?);
+        dwfl.report_core(core, executable)?;
         Ok(dwfl)
     }
 
+    pub(crate) fn report_core(&self, core: &'elf Elf<'_>, executable: Option<&Path>) -> Result<()> {
+        let executable = executable.map(|path| CString::new(path.as_os_str().as_bytes()).unwrap());
+        let executable = executable
+            .as_ref()
+            .map_or(ptr::null(), |path| path.as_ptr());
+
+        ffi!(dwfl_core_file_report(
+            self.as_ptr(),
+            core.as_ptr(),
+            executable
+        ))?;
+        self.report_end()
+    }
+
+    fn report_end(&self) -> Result<()> {
+        ffi!(dwfl_report_end(self.as_ptr(), None, ptr::null_mut()))?;
+        Ok(())
+    }
+
     #[inline]
     pub fn dwarfs(&self) -> Dwarfs<'_> {
         Dwarfs {
@@ -56,9 +190,134 @@ impl Dwfl {
         }
     }
 
+    /// Iterate over the modules reported to this `Dwfl`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let exe = std::env::current_exe().unwrap();
+    /// let dwfl = libdwfl::Dwfl::open(&exe).unwrap();
+    /// for module in dwfl.modules() {
+    ///     let module = module.unwrap();
+    ///     assert_eq!(module.name().to_bytes(), exe.as_os_str().as_encoded_bytes());
+    ///     assert!(module.dwarf().is_ok());
+    /// }
+    /// ```
+    #[inline]
+    pub fn modules(&self) -> Modules<'_> {
+        Modules::new(self)
+    }
+
+    pub(crate) unsafe fn getmodules<'dwfl, F>(&'dwfl self, offset: isize, mut f: F) -> Result<isize>
+    where
+        F: FnMut(Module<'dwfl>) -> libc::c_uint,
+    {
+        let argp = &mut f as *mut F as *mut libc::c_void;
+        return ffi!(dwfl_getmodules(
+            self.as_ptr(),
+            Some(callback::<'dwfl, F>),
+            argp,
+            offset
+        ));
+
+        unsafe extern "C" fn callback<'dwfl, F>(
+            module: *mut ffi::Dwfl_Module,
+            _userdata: *mut *mut libc::c_void,
+            _name: *const libc::c_char,
+            _start: u64,
+            argp: *mut libc::c_void,
+        ) -> libc::c_int
+        where
+            F: FnMut(Module<'dwfl>) -> libc::c_uint,
+        {
+            let f = &mut *(argp as *mut F);
+            f(Module::from_raw(module)) as libc::c_int
+        }
+    }
+
+    /// Find the DWARF debuginfo of the module containing an address.
+    ///
+    /// Returns `None` if no module contains the address.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let exe = std::env::current_exe().unwrap();
+    /// let dwfl = libdwfl::Dwfl::open(exe).unwrap();
+    /// let module = dwfl.modules().next().unwrap().unwrap();
+    /// let start = module.address_range().start;
+    /// let dw = dwfl.dwarf_for_address(start).unwrap().unwrap();
+    /// assert_eq!(dw.bias(), module.bias().unwrap());
+    /// assert!(dwfl.dwarf_for_address(0).unwrap().is_none());
+    /// ```
+    pub fn dwarf_for_address(&self, addr: u64) -> Result<Option<ModuleDwarf<'_>>> {
+        let module = raw_ffi!(dwfl_addrmodule(self.as_ptr(), addr));
+        if module.is_null() {
+            return Ok(None);
+        }
+        let module = unsafe { Module::from_raw(module) };
+
+        let mut bias = 0;
+        let dwarf = ffi!(dwfl_addrdwarf(self.as_ptr(), addr, &mut bias))?;
+        Ok(Some(ModuleDwarf {
+            dwarf: unsafe { Dwarf::from_raw(dwarf) },
+            module,
+            name: module.name(),
+            bias,
+        }))
+    }
+
+    /// Find the module and ELF symbol containing an address.
+    ///
+    /// Returns `None` if no module or symbol contains the address.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[inline(never)]
+    /// fn here() {}
+    ///
+    /// let pid = std::process::id() as libc::pid_t;
+    /// let dwfl = libdwfl::Dwfl::from_pid(pid).unwrap();
+    /// let address = here as fn() as usize as u64;
+    /// let symbol = dwfl.symbolize(address).unwrap().unwrap();
+    /// assert!(symbol.name().to_str().unwrap().contains("here"));
+    /// assert_eq!(symbol.start(), address);
+    /// assert_eq!(symbol.offset(), 0);
+    /// ```
+    pub fn symbolize(&self, address: u64) -> Result<Option<Symbol<'_>>> {
+        let module = raw_ffi!(dwfl_addrmodule(self.as_ptr(), address));
+        if module.is_null() {
+            return Ok(None);
+        }
+        unsafe { Module::from_raw(module) }.addr_info(address)
+    }
+
+    /// Find the source line for an address.
+    ///
+    /// Returns `None` if no module or line contains the address.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// fn here() {}
+    ///
+    /// let pid = std::process::id() as libc::pid_t;
+    /// let dwfl = libdwfl::Dwfl::from_pid(pid).unwrap();
+    /// let line = dwfl.source_line(here as fn() as usize as u64).unwrap().unwrap();
+    /// println!("{:?}:{}", line.file(), line.line());
+    /// ```
+    pub fn source_line(&self, address: u64) -> Result<Option<SourceLine<'_>>> {
+        let module = raw_ffi!(dwfl_addrmodule(self.as_ptr(), address));
+        if module.is_null() {
+            return Ok(None);
+        }
+        unsafe { Module::from_raw(module) }.source_line(address)
+    }
+
     unsafe fn getdwarf<'dwfl, F>(&'dwfl self, offset: isize, mut f: F) -> Result<isize>
     where
-        F: FnMut(Dwarf<'dwfl>) -> libc::c_uint,
+        F: FnMut(ModuleDwarf<'dwfl>) -> libc::c_uint,
     {
         let argp = &mut f as *mut F as *mut libc::c_void;
         return ffi!(dwfl_getdwarf(
@@ -69,42 +328,258 @@ impl Dwfl {
         ));
 
         unsafe extern "C" fn callback<'dwfl, F>(
-            _module: *mut ffi::Dwfl_Module,
+            module: *mut ffi::Dwfl_Module,
             _userdata: *mut *mut libc::c_void,
-            _name: *const libc::c_char,
+            name: *const libc::c_char,
             _start: u64,
             dwarf: *mut ffi::Dwarf,
-            _bias: u64,
+            bias: u64,
+            argp: *mut libc::c_void,
+        ) -> libc::c_int
+        where
+            F: FnMut(ModuleDwarf<'dwfl>) -> libc::c_uint,
+        {
+            let f = &mut *(argp as *mut F);
+            f(ModuleDwarf {
+                dwarf: Dwarf::from_raw(dwarf),
+                module: Module::from_raw(module),
+                name: CStr::from_ptr(name),
+                bias,
+            }) as libc::c_int
+        }
+    }
+
+    /// Attach to the threads of a live process for unwinding, normally one
+    /// opened with `from_pid`.
+    ///
+    /// Each thread is stopped with `ptrace` while it is unwound, unless
+    /// `assume_ptrace_stopped` says the caller has already done so.
+    pub fn attach_pid(&mut self, pid: libc::pid_t, assume_ptrace_stopped: bool) -> Result<()> {
+        proc_result(raw_ffi!(dwfl_linux_proc_attach(
+            self.as_ptr(),
+            pid,
+            assume_ptrace_stopped
+        )))
+    }
+
+    /// Attach to the threads of a core file for unwinding, normally one opened
+    /// with `from_core`, and return the process ID it recorded.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let core = libelf::Elf::open("core").unwrap();
+    /// let mut dwfl = libdwfl::Dwfl::from_core(&core, None).unwrap();
+    /// dwfl.attach_core(&core).unwrap();
+    /// for tid in dwfl.threads().unwrap() {
+    ///     println!("TID {}:", tid);
+    ///     dwfl.for_each_thread_frame(tid, |frame| {
+    ///         println!("{:#x}", frame.pc()?);
+    ///         Ok(true)
+    ///     })
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn attach_core(&mut self, core: &'elf Elf<'_>) -> Result<libc::pid_t> {
+        ffi!(dwfl_core_file_attach(self.as_ptr(), core.as_ptr()))
+    }
+
+    /// Attach Rust callbacks that supply the threads of a process for
+    /// unwinding.
+    ///
+    /// The architecture is taken from `elf` if given, or else from the
+    /// reported modules, and `pid` is what `Dwfl::pid` will report.
+    pub fn attach_state<T>(
+        &mut self,
+        elf: Option<&'elf Elf<'_>>,
+        pid: libc::pid_t,
+        callbacks: T,
+    ) -> Result<()>
+    where
+        T: ThreadCallbacks + 'elf,
+    {
+        let elf = elf.map_or(ptr::null_mut(), |elf| elf.as_ptr());
+        let state = Box::into_raw(CallbackState::new(callbacks));
+        let attached = raw_ffi!(dwfl_attach_state(
+            self.as_ptr(),
+            elf,
+            pid,
+            (*state).table(),
+            state.cast()
+        ));
+        if attached {
+            // No callbacks run until threads are requested.
+            CallbackState::register(self.as_ptr(), state);
+            Ok(())
+        } else {
+            // libdwfl only calls `detach` once it has attached.
+            drop(unsafe { Box::from_raw(state) });
+            Err(Error::last())
+        }
+    }
+
+    /// Get the process ID that this `Dwfl` is attached to, if any.
+    #[inline]
+    pub fn pid(&self) -> Option<libc::pid_t> {
+        let pid = raw_ffi!(dwfl_pid(self.as_ptr()));
+        if pid > 0 {
+            Some(pid)
+        } else {
+            None
+        }
+    }
+
+    /// Get the IDs of the threads of the attached process or core file.
+    pub fn threads(&self) -> Result<Vec<libc::pid_t>> {
+        let mut tids = Vec::new();
+        self.for_each_thread(|thread| {
+            tids.push(thread.tid());
+            Ok(true)
+        })?;
+        Ok(tids)
+    }
+
+    /// Visit each thread of the attached process or core file.
+    pub fn for_each_thread<F>(&self, mut f: F) -> Result<()>
+    where
+        F: FnMut(&Thread<'_>) -> Result<bool>,
+    {
+        let mut guard = CallbackGuard::new();
+        let mut result = Ok(());
+
+        unsafe {
+            self.getthreads(|thread| {
+                guard.call(ffi::DWARF_CB_ABORT, || dwarf_cb_map(f(thread), &mut result))
+            })?;
+        }
+
+        result
+    }
+
+    /// Unwind the stack of one thread, visiting each frame from the innermost.
+    ///
+    /// Frames visited before an unwinding error are still passed to `f`, and
+    /// then the error is returned.
+    pub fn for_each_thread_frame<F>(&self, tid: libc::pid_t, mut f: F) -> Result<()>
+    where
+        F: FnMut(&Frame<'_>) -> Result<bool>,
+    {
+        let mut guard = CallbackGuard::new();
+        let mut result = Ok(());
+
+        unsafe {
+            self.getthread_frames(tid, |frame| {
+                guard.call(ffi::DWARF_CB_ABORT, || dwarf_cb_map(f(frame), &mut result))
+            })?;
+        }
+
+        result
+    }
+
+    unsafe fn getthreads<F>(&self, mut f: F) -> Result<libc::c_int>
+    where
+        F: FnMut(&Thread<'_>) -> libc::c_uint,
+    {
+        let argp = &mut f as *mut F as *mut libc::c_void;
+        return ffi!(dwfl_getthreads(self.as_ptr(), Some(callback::<F>), argp));
+
+        unsafe extern "C" fn callback<F>(
+            thread: *mut ffi::Dwfl_Thread,
             argp: *mut libc::c_void,
         ) -> libc::c_int
         where
-            F: FnMut(Dwarf<'dwfl>) -> libc::c_uint,
+            F: FnMut(&Thread<'_>) -> libc::c_uint,
         {
             let f = &mut *(argp as *mut F);
-            f(Dwarf::from_raw(dwarf)) as libc::c_int
+            f(&Thread::new(thread)) as libc::c_int
         }
     }
 
+    unsafe fn getthread_frames<F>(&self, tid: libc::pid_t, mut f: F) -> Result<libc::c_int>
+    where
+        F: FnMut(&Frame<'_>) -> libc::c_uint,
+    {
+        let argp = &mut f as *mut F as *mut libc::c_void;
+        ffi!(dwfl_getthread_frames(
+            self.as_ptr(),
+            tid,
+            Some(frame_callback::<F>),
+            argp
+        ))
+    }
+
     #[inline]
     pub fn as_ptr(&self) -> *mut ffi::Dwfl {
         self.inner
     }
 }
 
-impl Drop for Dwfl {
+/// Check the result of the `dwfl_linux_proc_*` functions, which return a
+/// positive `errno` value for system errors.
+fn proc_result(rc: libc::c_int) -> Result<()> {
+    if rc > 0 {
+        Err(io::Error::from_raw_os_error(rc).into())
+    } else {
+        rc.into_result()?;
+        Ok(())
+    }
+}
+
+impl<'elf> Drop for Dwfl<'elf> {
     #[inline]
     fn drop(&mut self) {
-        raw_ffi!(dwfl_end(self.as_ptr()));
+        // Thread callbacks dropped here may panic, but resuming that from
+        // `drop` could abort while unwinding, so it's discarded.
+        unsafe { ffi::dwfl_end(self.as_ptr()) };
+        discard_panic();
+    }
+}
+
+/// The DWARF debuginfo of a module, as yielded by `Dwfl::dwarfs`.
+#[derive(Debug)]
+pub struct ModuleDwarf<'dwfl> {
+    dwarf: Dwarf<'dwfl>,
+    module: Module<'dwfl>,
+    name: &'dwfl CStr,
+    bias: u64,
+}
+
+impl<'dwfl> ModuleDwarf<'dwfl> {
+    #[inline]
+    pub fn dwarf(&self) -> &Dwarf<'dwfl> {
+        &self.dwarf
+    }
+
+    #[inline]
+    pub fn into_dwarf(self) -> Dwarf<'dwfl> {
+        self.dwarf
+    }
+
+    #[inline]
+    pub fn module(&self) -> Module<'dwfl> {
+        self.module
+    }
+
+    #[inline]
+    pub fn name(&self) -> &'dwfl CStr {
+        self.name
+    }
+
+    /// Get the bias to add to addresses in this DWARF for the module's
+    /// actual load address.
+    #[inline]
+    pub fn bias(&self) -> u64 {
+        self.bias
     }
 }
 
 pub struct Dwarfs<'dwfl> {
-    dwfl: &'dwfl Dwfl,
+    dwfl: &'dwfl Dwfl<'dwfl>,
     offset: isize,
 }
 
 impl<'dwfl> Iterator for Dwarfs<'dwfl> {
-    type Item = Result<Dwarf<'dwfl>>;
+    type Item = Result<ModuleDwarf<'dwfl>>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let mut dwarf = None;
@@ -8,7 +8,10 @@ use std::result;
 macro_rules! raw_ffi {
     ($func:ident ($($arg:expr),*)) => ({
         #[allow(unused_unsafe)]
-        unsafe { ffi::$func($($arg),*) }
+        let result = unsafe { ffi::$func($($arg),*) };
+        // Rust callbacks may have panicked within libdwfl.
+        crate::callback::resume_panic();
+        result
     })
 }
 
@@ -77,9 +80,18 @@ pub struct Error {
 #[derive(Debug)]
 enum ErrorKind {
     Dwfl(libc::c_int),
+    Dw(libdw::Error),
     Io(io::Error),
 }
 
+impl From<libdw::Error> for Error {
+    fn from(error: libdw::Error) -> Error {
+        Error {
+            kind: ErrorKind::Dw(error),
+        }
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(error: io::Error) -> Error {
         Error {
@@ -124,6 +136,7 @@ impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self.kind {
             ErrorKind::Dwfl(_) => None,
+            ErrorKind::Dw(ref error) => Some(error),
             ErrorKind::Io(ref error) => Some(error),
         }
     }
@@ -140,6 +153,7 @@ impl fmt::Display for Error {
                     Err(_) => fmt::Debug::fmt(msg, f),
                 }
             }
+            ErrorKind::Dw(ref error) => fmt::Display::fmt(&error, f),
             ErrorKind::Io(ref error) => fmt::Display::fmt(&error, f),
         }
     }
@@ -4,5 +4,25 @@ use libdw_sys as ffi;
 mod error;
 pub use crate::error::{Error, Result};
 
+mod builder;
+pub use crate::builder::DwflBuilder;
+
+mod callback;
+
 mod dwfl;
-pub use crate::dwfl::Dwfl;
+pub use crate::dwfl::{Dwarfs, Dwfl, ModuleDwarf};
+
+mod module;
+pub use crate::module::{Module, Modules};
+
+mod line;
+pub use crate::line::SourceLine;
+
+mod symbol;
+pub use crate::symbol::Symbol;
+
+mod symbolizer;
+pub use crate::symbolizer::{SymbolizedFrame, Symbolizer};
+
+mod thread;
+pub use crate::thread::{Frame, Thread, ThreadCallbacks, ThreadRegisters};
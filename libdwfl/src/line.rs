@@ -0,0 +1,270 @@
+use crate::ffi;
+
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::marker::PhantomData;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::ptr;
+use std::slice;
+
+use super::Module;
+use super::{Error, Result};
+
+/// A source line record for an address, as found by `Module::source_line`.
+#[derive(Clone, Copy)]
+pub struct SourceLine<'dwfl> {
+    inner: *mut ffi::Dwfl_Line,
+    phantom: PhantomData<Module<'dwfl>>,
+}
+
+impl<'dwfl> fmt::Debug for SourceLine<'dwfl> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SourceLine")
+            .field("address", &format_args!("{:#x}", self.address()))
+            .field("file", &self.file())
+            .field("line", &self.line())
+            .field("column", &self.column())
+            .finish()
+    }
+}
+
+impl<'dwfl> SourceLine<'dwfl> {
+    #[inline]
+    fn new(line: *mut ffi::Dwfl_Line) -> SourceLine<'dwfl> {
+        SourceLine {
+            inner: line,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Find the source line for an address in a module.
+    pub(crate) fn lookup(module: Module<'dwfl>, address: u64) -> Result<Option<SourceLine<'dwfl>>> {
+        // Report missing debuginfo as an error, rather than just an address
+        // without a line.
+        module.dwarf()?;
+
+        let line = raw_ffi!(dwfl_module_getsrc(module.as_ptr(), address));
+        if line.is_null() {
+            Ok(None)
+        } else {
+            Ok(Some(SourceLine::new(line)))
+        }
+    }
+
+    /// Find the line records for a source file and line in a module.
+    pub(crate) fn for_file(
+        module: Module<'dwfl>,
+        file: &Path,
+        line: u32,
+        column: u32,
+    ) -> Result<Vec<SourceLine<'dwfl>>> {
+        module.dwarf()?;
+
+        let file = CString::new(file.as_os_str().as_bytes()).unwrap();
+        let mut srcs = ptr::null_mut();
+        let mut nsrcs = 0;
+        let rc = raw_ffi!(dwfl_module_getsrc_file(
+            module.as_ptr(),
+            file.as_ptr(),
+            line as libc::c_int,
+            column as libc::c_int,
+            &mut srcs,
+            &mut nsrcs
+        ));
+
+        if rc < 0 {
+            // libdwfl reports an error when nothing matches, which we'd rather
+            // just return as an empty list.  Its only other failure is reading
+            // the line tables, so check those for a real error, after clearing
+            // this one.
+            let _ = Error::last();
+            check_lines(module)?;
+            return Ok(Vec::new());
+        }
+        if srcs.is_null() {
+            return Ok(Vec::new());
+        }
+        let lines = unsafe { slice::from_raw_parts(srcs, nsrcs) }
+            .iter()
+            .map(|&line| SourceLine::new(line))
+            .collect();
+        unsafe { libc::free(srcs.cast()) };
+        Ok(lines)
+    }
+
+    /// Get the module containing this line.
+    #[inline]
+    pub fn module(&self) -> Module<'dwfl> {
+        unsafe { Module::from_raw(raw_ffi!(dwfl_linemodule(self.as_ptr()))) }
+    }
+
+    /// Get the address of this line, including the module's load bias.
+    #[inline]
+    pub fn address(&self) -> u64 {
+        self.info().address
+    }
+
+    /// Get the source file name, which may be relative to `comp_dir`.
+    #[inline]
+    pub fn file(&self) -> &'dwfl CStr {
+        let file = self.info().file;
+        if file.is_null() {
+            c""
+        } else {
+            unsafe { CStr::from_ptr(file) }
+        }
+    }
+
+    /// Get the line number, or 0 if unknown.
+    #[inline]
+    pub fn line(&self) -> u32 {
+        self.info().line as u32
+    }
+
+    /// Get the column number, or 0 if unknown.
+    #[inline]
+    pub fn column(&self) -> u32 {
+        self.info().column as u32
+    }
+
+    /// Get the compilation directory of the unit containing this line.
+    #[inline]
+    pub fn comp_dir(&self) -> Option<&'dwfl CStr> {
+        let dir = raw_ffi!(dwfl_line_comp_dir(self.as_ptr()));
+        if dir.is_null() {
+            None
+        } else {
+            Some(unsafe { CStr::from_ptr(dir) })
+        }
+    }
+
+    /// Check whether this line is a recommended breakpoint location.
+    #[inline]
+    pub fn is_stmt(&self) -> bool {
+        self.flag(ffi::dwarf_linebeginstatement)
+    }
+
+    /// Check whether this line begins a basic block.
+    #[inline]
+    pub fn basic_block(&self) -> bool {
+        self.flag(ffi::dwarf_lineblock)
+    }
+
+    /// Check whether this line ends a sequence of addresses.
+    #[inline]
+    pub fn end_sequence(&self) -> bool {
+        self.flag(ffi::dwarf_lineendsequence)
+    }
+
+    /// Check whether this line is the end of a function prologue.
+    #[inline]
+    pub fn prologue_end(&self) -> bool {
+        self.flag(ffi::dwarf_lineprologueend)
+    }
+
+    /// Check whether this line is the beginning of a function epilogue.
+    #[inline]
+    pub fn epilogue_begin(&self) -> bool {
+        self.flag(ffi::dwarf_lineepiloguebegin)
+    }
+
+    /// Get the discriminator of the block containing this line.
+    #[inline]
+    pub fn discriminator(&self) -> u32 {
+        let mut disc = 0;
+        raw_ffi!(dwarf_linediscriminator(self.dwarf_line(), &mut disc));
+        disc
+    }
+
+    fn flag(
+        &self,
+        f: unsafe extern "C" fn(*mut ffi::Dwarf_Line, *mut bool) -> libc::c_int,
+    ) -> bool {
+        let mut flag = false;
+        unsafe { f(self.dwarf_line(), &mut flag) };
+        flag
+    }
+
+    fn dwarf_line(&self) -> *mut ffi::Dwarf_Line {
+        let mut bias = 0;
+        raw_ffi!(dwfl_dwarf_line(self.as_ptr(), &mut bias))
+    }
+
+    fn info(&self) -> LineInfo {
+        let mut info = LineInfo {
+            file: ptr::null(),
+            address: 0,
+            line: 0,
+            column: 0,
+        };
+        info.file = raw_ffi!(dwfl_lineinfo(
+            self.as_ptr(),
+            &mut info.address,
+            &mut info.line,
+            &mut info.column,
+            ptr::null_mut(),
+            ptr::null_mut()
+        ));
+        info
+    }
+
+    #[inline]
+    pub fn as_ptr(&self) -> *mut ffi::Dwfl_Line {
+        self.inner
+    }
+}
+
+struct LineInfo {
+    file: *const libc::c_char,
+    address: u64,
+    line: libc::c_int,
+    column: libc::c_int,
+}
+
+/// Read the line table of every unit in `module`, reporting any error.
+fn check_lines(module: Module<'_>) -> Result<()> {
+    let mut cu = ptr::null_mut();
+    let mut bias = 0;
+    loop {
+        cu = raw_ffi!(dwfl_module_nextcu(module.as_ptr(), cu, &mut bias));
+        if cu.is_null() {
+            return Error::check().map_or(Ok(()), Err);
+        }
+        let mut nlines = 0;
+        ffi!(dwfl_getsrclines(cu, &mut nlines))?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Dwfl;
+
+    #[test]
+    fn own_lines() {
+        #[inline(never)]
+        fn here() -> u32 {
+            line!()
+        }
+
+        let dwfl = Dwfl::from_pid(std::process::id() as libc::pid_t).unwrap();
+        let line = dwfl
+            .source_line(here as fn() -> u32 as usize as u64)
+            .unwrap()
+            .unwrap();
+        assert!(line.file().to_str().unwrap().ends_with(file!()));
+        assert!((here() - 1..=here() + 1).contains(&line.line()));
+        assert!(line.comp_dir().is_some());
+
+        let module = line.module();
+        let lines = module.lines_for_file(file!(), line.line(), 0).unwrap();
+        assert!(lines.iter().any(|l| l.address() == line.address()));
+        for l in lines {
+            assert!(l.line() >= line.line());
+        }
+        assert!(module
+            .lines_for_file("no-such-file.rs", 1, 0)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,284 @@
+use crate::ffi;
+
+use std::ffi::{CStr, OsStr};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::ptr;
+use std::slice;
+
+use libdw::Dwarf;
+use libelf::Elf;
+
+use super::Dwfl;
+use super::Result;
+use super::SourceLine;
+use super::Symbol;
+
+/// A module reported to a `Dwfl` session, like an executable or shared library.
+#[derive(Clone, Copy)]
+pub struct Module<'dwfl> {
+    inner: *mut ffi::Dwfl_Module,
+    phantom: PhantomData<&'dwfl Dwfl<'dwfl>>,
+}
+
+impl<'dwfl> fmt::Debug for Module<'dwfl> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let range = self.address_range();
+        f.debug_struct("Module")
+            .field("name", &self.name())
+            .field("start", &format_args!("{:#x}", range.start))
+            .field("end", &format_args!("{:#x}", range.end))
+            .finish()
+    }
+}
+
+impl<'dwfl> Module<'dwfl> {
+    /// Create a `Module` from a raw FFI pointer.
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because there is no guarantee that the given
+    /// pointer is a valid `Dwfl_Module` handle, nor whether the lifetime
+    /// inferred is appropriate.
+    #[inline]
+    pub unsafe fn from_raw(module: *mut ffi::Dwfl_Module) -> Module<'dwfl> {
+        Module {
+            inner: module,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Get the name of this module, as it was reported.
+    #[inline]
+    pub fn name(&self) -> &'dwfl CStr {
+        unsafe { CStr::from_ptr(self.info().name) }
+    }
+
+    /// Get the range of addresses covered by this module.
+    #[inline]
+    pub fn address_range(&self) -> Range<u64> {
+        let info = self.info();
+        info.start..info.end
+    }
+
+    /// Get the path of the main ELF file, if it has been found yet.
+    #[inline]
+    pub fn main_file(&self) -> Option<&'dwfl Path> {
+        unsafe { to_path(self.info().mainfile) }
+    }
+
+    /// Get the path of the separate debuginfo file, if it has been found yet.
+    ///
+    /// This is `None` if the debuginfo is in the main file.
+    #[inline]
+    pub fn debug_file(&self) -> Option<&'dwfl Path> {
+        unsafe { to_path(self.info().debugfile) }
+    }
+
+    /// Get the load bias of this module, finding its ELF file if necessary.
+    #[inline]
+    pub fn bias(&self) -> Result<u64> {
+        let mut bias = 0;
+        ffi!(dwfl_module_getelf(self.as_ptr(), &mut bias))?;
+        Ok(bias)
+    }
+
+    /// Get the GNU build-id of this module, if it has one.
+    pub fn build_id(&self) -> Result<Option<&'dwfl [u8]>> {
+        let mut bits = ptr::null();
+        let mut vaddr = 0;
+        let len = ffi!(dwfl_module_build_id(self.as_ptr(), &mut bits, &mut vaddr))?;
+        if len == 0 || bits.is_null() {
+            Ok(None)
+        } else {
+            Ok(Some(unsafe { slice::from_raw_parts(bits, len as usize) }))
+        }
+    }
+
+    /// Get the main ELF file of this module, and the bias of its addresses.
+    #[inline]
+    pub fn elf(&self) -> Result<(Elf<'dwfl>, u64)> {
+        let mut bias = 0;
+        let elf = ffi!(dwfl_module_getelf(self.as_ptr(), &mut bias))?;
+        Ok((unsafe { Elf::from_raw(elf) }, bias))
+    }
+
+    /// Get the DWARF debuginfo of this module, and the bias of its addresses.
+    #[inline]
+    pub fn dwarf(&self) -> Result<(Dwarf<'dwfl>, u64)> {
+        let mut bias = 0;
+        let dwarf = ffi!(dwfl_module_getdwarf(self.as_ptr(), &mut bias))?;
+        Ok((unsafe { Dwarf::from_raw(dwarf) }, bias))
+    }
+
+    /// Find the ELF symbol containing an address.
+    ///
+    /// Returns `None` if no symbol contains the address.
+    #[inline]
+    pub fn addr_info(&self, address: u64) -> Result<Option<Symbol<'dwfl>>> {
+        Symbol::lookup(*self, address)
+    }
+
+    /// Find the source line for an address.
+    ///
+    /// Returns `None` if no line contains the address.
+    #[inline]
+    pub fn source_line(&self, address: u64) -> Result<Option<SourceLine<'dwfl>>> {
+        SourceLine::lookup(*self, address)
+    }
+
+    /// Find the line records for a line in a source file, or else for the
+    /// nearest following line with code.
+    ///
+    /// A file name without a directory matches any directory, and a `column`
+    /// of zero matches any column.
+    #[inline]
+    pub fn lines_for_file<P: AsRef<Path>>(
+        &self,
+        file: P,
+        line: u32,
+        column: u32,
+    ) -> Result<Vec<SourceLine<'dwfl>>> {
+        SourceLine::for_file(*self, file.as_ref(), line, column)
+    }
+
+    fn info(&self) -> ModuleInfo {
+        let mut info = ModuleInfo {
+            name: ptr::null(),
+            start: 0,
+            end: 0,
+            mainfile: ptr::null(),
+            debugfile: ptr::null(),
+        };
+        info.name = raw_ffi!(dwfl_module_info(
+            self.as_ptr(),
+            ptr::null_mut(),
+            &mut info.start,
+            &mut info.end,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &mut info.mainfile,
+            &mut info.debugfile
+        ));
+        info
+    }
+
+    #[inline]
+    pub fn as_ptr(&self) -> *mut ffi::Dwfl_Module {
+        self.inner
+    }
+}
+
+struct ModuleInfo {
+    name: *const libc::c_char,
+    start: u64,
+    end: u64,
+    mainfile: *const libc::c_char,
+    debugfile: *const libc::c_char,
+}
+
+unsafe fn to_path<'a>(s: *const libc::c_char) -> Option<&'a Path> {
+    if s.is_null() {
+        None
+    } else {
+        Some(Path::new(OsStr::from_bytes(CStr::from_ptr(s).to_bytes())))
+    }
+}
+
+/// An iterator over the modules of a `Dwfl`.
+pub struct Modules<'dwfl> {
+    dwfl: &'dwfl Dwfl<'dwfl>,
+    offset: isize,
+}
+
+impl<'dwfl> Modules<'dwfl> {
+    #[inline]
+    pub(crate) fn new(dwfl: &'dwfl Dwfl<'dwfl>) -> Self {
+        Modules { dwfl, offset: 0 }
+    }
+}
+
+impl<'dwfl> Iterator for Modules<'dwfl> {
+    type Item = Result<Module<'dwfl>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut module = None;
+        let rc = unsafe {
+            self.dwfl.getmodules(self.offset, |m| {
+                module = Some(m);
+                libdw::raw::DWARF_CB_ABORT
+            })
+        };
+
+        match rc {
+            Ok(offset) => {
+                self.offset = offset;
+                module.map(Ok)
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Dwfl;
+
+    #[inline(never)]
+    fn here() -> u64 {
+        here as fn() -> u64 as usize as u64
+    }
+
+    #[test]
+    fn borrowed_alt() {
+        let dwfl = Dwfl::from_pid(std::process::id() as libc::pid_t).unwrap();
+        let module_dwarf = dwfl.dwarf_for_address(here()).unwrap().unwrap();
+        let (mut dwarf, _) = module_dwarf.module().dwarf().unwrap();
+        assert_eq!(dwarf.as_ptr(), module_dwarf.dwarf().as_ptr());
+
+        // libdwfl owns the module's Dwarf, so it can't take a new alternate.
+        assert!(dwarf.setup_alt().is_err());
+        assert!(module_dwarf.into_dwarf().setup_alt().is_err());
+        drop(dwarf);
+        assert!(dwfl.dwarf_for_address(here()).unwrap().is_some());
+    }
+
+    #[test]
+    fn own_module() {
+        let dwfl = Dwfl::from_pid(std::process::id() as libc::pid_t).unwrap();
+        let mut found = dwfl
+            .modules()
+            .map(Result::unwrap)
+            .filter(|module| module.address_range().contains(&here()));
+        let module = found.next().unwrap();
+        assert!(found.next().is_none());
+
+        let bias = module.bias().unwrap();
+        assert_eq!(module.elf().unwrap().1, bias);
+        assert_eq!(module.dwarf().unwrap().1, bias);
+        let exe = std::env::current_exe().unwrap().canonicalize().unwrap();
+        let main_file = module.main_file().unwrap().canonicalize().unwrap();
+        assert_eq!(main_file, exe);
+        assert!(module.build_id().unwrap().is_none_or(|id| !id.is_empty()));
+
+        let module_dwarf = dwfl
+            .dwarfs()
+            .map(Result::unwrap)
+            .find(|dwarf| dwarf.module().as_ptr() == module.as_ptr())
+            .unwrap();
+        assert_eq!(module_dwarf.name(), module.name());
+        assert_eq!(module_dwarf.bias(), bias);
+
+        // The same module is found from a saved copy of the maps.  It may
+        // end later, if the file has since been mapped again to read it.
+        let maps = std::fs::read("/proc/self/maps").unwrap();
+        let dwfl = Dwfl::from_proc_maps(&maps[..]).unwrap();
+        let symbol = dwfl.symbolize(here()).unwrap().unwrap();
+        assert_eq!(symbol.module().name(), module.name());
+        let range = symbol.module().address_range();
+        assert_eq!(range.start, module.address_range().start);
+    }
+}
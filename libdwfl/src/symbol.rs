@@ -0,0 +1,189 @@
+use crate::ffi;
+
+use std::ffi::CStr;
+use std::mem;
+use std::ptr;
+
+use libelf::raw as elf;
+
+use super::Module;
+use super::Result;
+
+/// An ELF symbol containing an address, as found by `Module::addr_info`.
+///
+/// All addresses include the module's load bias.
+#[derive(Debug, Clone)]
+pub struct Symbol<'dwfl> {
+    module: Module<'dwfl>,
+    name: &'dwfl CStr,
+    address: u64,
+    start: u64,
+    size: u64,
+    info: u8,
+    section_index: u32,
+    section_name: Option<&'dwfl CStr>,
+    bias: u64,
+}
+
+impl<'dwfl> Symbol<'dwfl> {
+    /// Look up the symbol containing an address in a module.
+    pub(crate) fn lookup(module: Module<'dwfl>, address: u64) -> Result<Option<Symbol<'dwfl>>> {
+        // Make sure a missing symbol table is reported as an error, rather
+        // than just an address without a symbol.
+        ffi!(dwfl_module_getsymtab(module.as_ptr()))?;
+
+        let mut offset = 0;
+        let mut sym: elf::GElf_Sym = unsafe { mem::zeroed() };
+        let mut shndx = 0;
+        let mut elf = ptr::null_mut();
+        let mut bias = 0;
+        let name = raw_ffi!(dwfl_module_addrinfo(
+            module.as_ptr(),
+            address,
+            &mut offset,
+            &mut sym,
+            &mut shndx,
+            &mut elf,
+            &mut bias
+        ));
+        if name.is_null() {
+            return Ok(None);
+        }
+
+        Ok(Some(Symbol {
+            module,
+            name: unsafe { CStr::from_ptr(name) },
+            address,
+            start: address.wrapping_sub(offset),
+            size: sym.st_size,
+            info: sym.st_info,
+            section_index: shndx,
+            section_name: unsafe { section_name(elf, shndx) },
+            bias,
+        }))
+    }
+
+    /// Get the module containing this symbol.
+    #[inline]
+    pub fn module(&self) -> Module<'dwfl> {
+        self.module
+    }
+
+    #[inline]
+    pub fn name(&self) -> &'dwfl CStr {
+        self.name
+    }
+
+    /// Get the address that was looked up.
+    #[inline]
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// Get the starting address of the symbol.
+    #[inline]
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Get the size of the symbol.
+    ///
+    /// This may be zero for symbols like assembly labels, which libdwfl only
+    /// matches when no sized symbol contains the address.
+    #[inline]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Get the offset of the address from the start of the symbol.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.address - self.start
+    }
+
+    /// Get the symbol binding, an `STB_*` value.
+    #[inline]
+    pub fn binding(&self) -> u8 {
+        self.info >> 4
+    }
+
+    /// Get the symbol type, an `STT_*` value.
+    #[inline]
+    pub fn symbol_type(&self) -> u8 {
+        self.info & 0xf
+    }
+
+    /// Get the index of the section containing the symbol, which may be a
+    /// special index like `SHN_ABS`.
+    #[inline]
+    pub fn section_index(&self) -> u32 {
+        self.section_index
+    }
+
+    /// Get the name of the section containing the symbol, if it has one.
+    #[inline]
+    pub fn section_name(&self) -> Option<&'dwfl CStr> {
+        self.section_name
+    }
+
+    /// Get the bias between the symbol table's values and actual addresses.
+    #[inline]
+    pub fn bias(&self) -> u64 {
+        self.bias
+    }
+}
+
+unsafe fn section_name<'dwfl>(elf: *mut elf::Elf, shndx: u32) -> Option<&'dwfl CStr> {
+    if elf.is_null() || shndx == elf::SHN_UNDEF || shndx == elf::SHN_ABS {
+        return None;
+    }
+
+    let mut shstrndx = 0;
+    if elf::elf_getshdrstrndx(elf, &mut shstrndx) != 0 {
+        return None;
+    }
+    let scn = elf::elf_getscn(elf, shndx as usize);
+    let mut shdr = mem::zeroed();
+    if scn.is_null() || elf::gelf_getshdr(scn, &mut shdr).is_null() {
+        return None;
+    }
+    let name = elf::elf_strptr(elf, shstrndx, shdr.sh_name as usize);
+    if name.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Dwfl;
+    use libelf::raw as elf;
+
+    #[inline(never)]
+    fn here() -> u64 {
+        here as fn() -> u64 as usize as u64
+    }
+
+    #[test]
+    fn own_symbol() {
+        let dwfl = Dwfl::from_pid(std::process::id() as libc::pid_t).unwrap();
+        let symbol = dwfl.symbolize(here() + 1).unwrap().unwrap();
+        assert!(symbol.name().to_str().unwrap().contains("here"));
+        assert_eq!(symbol.address(), here() + 1);
+        assert_eq!(symbol.start(), here());
+        assert_eq!(symbol.offset(), 1);
+        assert!(symbol.size() > 1);
+        assert_eq!(u32::from(symbol.symbol_type()), elf::STT_FUNC);
+        assert_eq!(symbol.section_name().unwrap().to_bytes(), b".text");
+        assert_ne!(symbol.section_index(), elf::SHN_UNDEF);
+
+        let module = symbol.module();
+        assert!(module.address_range().contains(&here()));
+        assert_eq!(symbol.bias(), module.bias().unwrap());
+
+        let end = symbol.start() + symbol.size();
+        let next = module.addr_info(end).unwrap();
+        assert!(next.is_none_or(|next| next.start() != symbol.start()));
+    }
+}
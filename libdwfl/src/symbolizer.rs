@@ -0,0 +1,382 @@
+use crate::ffi;
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::ffi::{CStr, OsStr};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+use libdw::{Die, DwAt, DwTag, Dwarf};
+
+use super::Dwfl;
+use super::Module;
+use super::Result;
+use super::Symbol;
+
+/// One function level for a symbolized address.
+///
+/// The location is where this function is executing: the source line of the
+/// address for the innermost frame, or the call site of the next inner
+/// frame for the functions it was inlined into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolizedFrame {
+    function: Option<String>,
+    file: Option<PathBuf>,
+    line: u32,
+    column: u32,
+    inlined: bool,
+}
+
+impl SymbolizedFrame {
+    /// Get the function name, demangled if possible.
+    #[inline]
+    pub fn function(&self) -> Option<&str> {
+        self.function.as_deref()
+    }
+
+    #[inline]
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Get the line number, or 0 if unknown.
+    #[inline]
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// Get the column number, or 0 if unknown.
+    #[inline]
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+
+    /// Check whether this function was inlined into the next frame.
+    #[inline]
+    pub fn is_inlined(&self) -> bool {
+        self.inlined
+    }
+}
+
+/// Symbolize addresses into function names and source locations, including
+/// inlined functions, like `eu-addr2line -f -i -C`.
+///
+/// The functions of each module are indexed the first time it is used, so
+/// later lookups in the same module don't have to search its whole DWARF.
+pub struct Symbolizer<'dwfl> {
+    dwfl: &'dwfl Dwfl<'dwfl>,
+    // `None` marks modules without DWARF, which only have ELF symbols.
+    modules: HashMap<usize, Option<FunctionIndex>>,
+    demangle: bool,
+}
+
+impl<'dwfl> Symbolizer<'dwfl> {
+    #[inline]
+    pub fn new(dwfl: &'dwfl Dwfl<'dwfl>) -> Self {
+        Symbolizer {
+            dwfl,
+            modules: HashMap::new(),
+            demangle: true,
+        }
+    }
+
+    /// Set whether to demangle C++ and Rust function names, which is the default.
+    #[inline]
+    pub fn demangle(&mut self, demangle: bool) -> &mut Self {
+        self.demangle = demangle;
+        self
+    }
+
+    /// Symbolize an address, returning the innermost inlined function first
+    /// and the containing concrete function last.
+    ///
+    /// Returns an empty list if no module contains the address.
+    pub fn symbolize(&mut self, address: u64) -> Result<Vec<SymbolizedFrame>> {
+        let module = raw_ffi!(dwfl_addrmodule(self.dwfl.as_ptr(), address));
+        if module.is_null() {
+            return Ok(Vec::new());
+        }
+        let module = unsafe { Module::from_raw(module) };
+
+        let dwarf = module.dwarf().ok();
+        let index = match self.modules.entry(module.as_ptr() as usize) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let index = match dwarf {
+                    Some((ref dwarf, _)) => Some(FunctionIndex::build(dwarf)?),
+                    None => None,
+                };
+                entry.insert(index)
+            }
+        };
+
+        // The line table gives the location of the innermost frame.
+        let line = module.source_line(address).ok().flatten();
+        let mut file = line.map(|line| path(line.file()));
+        let mut line_number = line.map_or(0, |line| line.line());
+        let mut column = line.map_or(0, |line| line.column());
+
+        let chain = match (&dwarf, index) {
+            (Some((dwarf, bias)), Some(index)) => {
+                let pc = address.wrapping_sub(*bias);
+                match index.lookup(pc) {
+                    Some(function) => inline_chain(function, pc)?,
+                    None => scope_chain(dwarf, pc)?,
+                }
+            }
+            _ => Vec::new(),
+        };
+
+        let mut frames = Vec::with_capacity(chain.len().max(1));
+        for die in &chain {
+            let inlined = die.tag()? == DwTag::INLINED_SUBROUTINE;
+            frames.push(SymbolizedFrame {
+                function: function_name(die).map(|name| self.name(name)),
+                file: file.take(),
+                line: line_number,
+                column,
+                inlined,
+            });
+            if inlined {
+                file = die.call_file().ok().map(path);
+                line_number = die.call_line().unwrap_or(0);
+                column = die.call_column().unwrap_or(0);
+            }
+        }
+
+        // Without DWARF scopes, fall back to the ELF symbol table.
+        if frames.is_empty() {
+            let symbol = Symbol::lookup(module, address).ok().flatten();
+            frames.push(SymbolizedFrame {
+                function: symbol.map(|symbol| self.name(symbol.name())),
+                file,
+                line: line_number,
+                column,
+                inlined: false,
+            });
+        }
+        Ok(frames)
+    }
+
+    fn name(&self, name: &CStr) -> String {
+        if self.demangle {
+            if let Some(demangled) = demangle(name) {
+                return demangled;
+            }
+        }
+        name.to_string_lossy().into_owned()
+    }
+}
+
+/// The address ranges of a module's concrete functions, sorted by address.
+struct FunctionIndex {
+    ranges: Vec<(u64, u64, ffi::Dwarf_Die)>,
+    // The greatest end of each range and all those before it, since ranges
+    // may overlap.
+    max_ends: Vec<u64>,
+}
+
+impl FunctionIndex {
+    fn build(dwarf: &Dwarf<'_>) -> Result<FunctionIndex> {
+        let mut ranges = Vec::new();
+        for cu in dwarf.compile_units() {
+            let mut tree = cu?.tree()?;
+            while let Some(die) = tree.next() {
+                let die = die?;
+                match die.tag()? {
+                    DwTag::SUBPROGRAM => {
+                        for range in die.ranges() {
+                            let range = range?;
+                            // Empty ranges, like discarded COMDAT copies,
+                            // would only hide real functions.
+                            if range.start < range.end {
+                                ranges.push((range.start, range.end, unsafe { *die.as_ptr() }));
+                            }
+                        }
+                        // Nested functions are left to the slower scope search.
+                        tree.skip_children();
+                    }
+                    DwTag::COMPILE_UNIT
+                    | DwTag::PARTIAL_UNIT
+                    | DwTag::NAMESPACE
+                    | DwTag::MODULE
+                    | DwTag::CLASS_TYPE
+                    | DwTag::STRUCTURE_TYPE
+                    | DwTag::UNION_TYPE => (),
+                    _ => tree.skip_children(),
+                }
+            }
+        }
+        ranges.sort_by_key(|&(start, end, _)| (start, end));
+        let max_ends = ranges
+            .iter()
+            .scan(0, |max, &(_, end, _)| {
+                *max = end.max(*max);
+                Some(*max)
+            })
+            .collect();
+        Ok(FunctionIndex { ranges, max_ends })
+    }
+
+    /// Find the function containing `pc`, preferring the latest start.
+    fn lookup<'dw>(&self, pc: u64) -> Option<Die<'dw>> {
+        let mut index = self.ranges.partition_point(|&(start, _, _)| start <= pc);
+        while index > 0 && pc < self.max_ends[index - 1] {
+            index -= 1;
+            let (_, end, die) = self.ranges[index];
+            if pc < end {
+                let function = Die::default();
+                unsafe { *function.as_ptr() = die };
+                return Some(function);
+            }
+        }
+        None
+    }
+}
+
+/// Find the inlined functions within `function` containing `pc`, innermost
+/// first, followed by `function` itself.
+fn inline_chain<'dw>(function: Die<'dw>, pc: u64) -> Result<Vec<Die<'dw>>> {
+    let mut chain = vec![function.clone()];
+    let mut scope = function;
+    'outer: loop {
+        for child in scope.iter_children() {
+            let child = child?;
+            match child.tag()? {
+                DwTag::INLINED_SUBROUTINE if child.has_pc(pc)? => {
+                    chain.push(child.clone());
+                    scope = child;
+                    continue 'outer;
+                }
+                DwTag::LEXICAL_BLOCK if child.has_pc(pc)? => {
+                    scope = child;
+                    continue 'outer;
+                }
+                _ => (),
+            }
+        }
+        break;
+    }
+    chain.reverse();
+    Ok(chain)
+}
+
+/// Find the chain of functions containing `pc` by searching all of the
+/// scopes in its unit.
+fn scope_chain<'dw>(dwarf: &'dw Dwarf<'_>, pc: u64) -> Result<Vec<Die<'dw>>> {
+    match Die::from_address(dwarf, pc) {
+        Ok(cudie) => Ok(cudie.scopes_at(pc)?.inline_chain()?),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+/// Get the linkage name of a function, or else its plain name.
+fn function_name<'dw>(die: &Die<'dw>) -> Option<&'dw CStr> {
+    die.attr_integrate(DwAt::LINKAGE_NAME)
+        .or_else(|_| die.attr_integrate(DwAt::MIPS_LINKAGE_NAME))
+        .and_then(|attr| attr.get_string())
+        .or_else(|_| die.name())
+        .ok()
+}
+
+fn demangle(name: &CStr) -> Option<String> {
+    if let Ok(s) = name.to_str() {
+        if let Ok(demangled) = rustc_demangle::try_demangle(s) {
+            return Some(format!("{:#}", demangled));
+        }
+    }
+    if let Ok(symbol) = cpp_demangle::Symbol::new(name.to_bytes()) {
+        return Some(symbol.to_string());
+    }
+    None
+}
+
+fn path(file: &CStr) -> PathBuf {
+    Path::new(OsStr::from_bytes(file.to_bytes())).to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Dwfl, Symbolizer};
+
+    #[test]
+    fn own_frames() {
+        #[inline(never)]
+        fn here() -> u32 {
+            line!()
+        }
+
+        let dwfl = Dwfl::from_pid(std::process::id() as libc::pid_t).unwrap();
+        let mut symbolizer = Symbolizer::new(&dwfl);
+        let address = here as fn() -> u32 as usize as u64;
+        let frames = symbolizer.symbolize(address).unwrap();
+        let frame = frames.last().unwrap();
+        assert!(!frame.is_inlined());
+        assert!(frame.function().unwrap().ends_with("own_frames::here"));
+        assert!(frame.file().unwrap().ends_with(file!()));
+        assert!((here() - 1..=here() + 1).contains(&frame.line()));
+
+        // Cached lookups give the same answer, and mangled names are kept
+        // when demangling is off.
+        assert_eq!(symbolizer.symbolize(address).unwrap(), frames);
+        let frames = symbolizer.demangle(false).symbolize(address).unwrap();
+        let mangled = frames.last().unwrap().function().unwrap();
+        assert!(mangled.starts_with("_R") || mangled.starts_with("_ZN"));
+        assert!(symbolizer.symbolize(0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn inlined_frames() {
+        use libdw::{Die, DwTag};
+        use std::hint::black_box;
+
+        #[inline(always)]
+        fn inner(x: u32) -> u32 {
+            black_box(x).wrapping_mul(3)
+        }
+
+        #[inline(never)]
+        fn outer(x: u32) -> u32 {
+            inner(x) + 1
+        }
+        let call_line = line!() - 2;
+        assert_eq!(outer(1), 4);
+
+        let dwfl = Dwfl::from_pid(std::process::id() as libc::pid_t).unwrap();
+        let address = outer as fn(u32) -> u32 as usize as u64;
+        let module = dwfl.dwarf_for_address(address).unwrap().unwrap();
+        let (dwarf, bias) = (module.dwarf(), module.bias());
+
+        // Find where `inner` was inlined into `outer`, walking the unit since
+        // libdw's scope search skips namespaces.
+        let pc = address - bias;
+        let outer_die = Die::from_address(dwarf, pc)
+            .unwrap()
+            .tree()
+            .map(|die| die.unwrap())
+            .find(|die| die.tag().unwrap() == DwTag::SUBPROGRAM && die.has_pc(pc).unwrap())
+            .unwrap();
+        let inlined = outer_die
+            .tree()
+            .map(|die| die.unwrap())
+            .find(|die| die.tag().unwrap() == DwTag::INLINED_SUBROUTINE)
+            .unwrap();
+        let pc = inlined.ranges().next().unwrap().unwrap().start;
+
+        let mut symbolizer = Symbolizer::new(&dwfl);
+        let frames = symbolizer.symbolize(pc + bias).unwrap();
+        assert_eq!(frames.len(), 2, "{:?}", frames);
+        assert!(frames[0].is_inlined());
+        assert!(frames[0]
+            .function()
+            .unwrap()
+            .ends_with("inlined_frames::inner"));
+        assert!(!frames[1].is_inlined());
+        assert!(frames[1]
+            .function()
+            .unwrap()
+            .ends_with("inlined_frames::outer"));
+        assert!(frames[1].file().unwrap().ends_with(file!()));
+        assert_eq!(frames[1].line(), call_line);
+    }
+}
@@ -0,0 +1,614 @@
+use crate::ffi;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+use super::callback::{catch_panic, dwarf_cb_map, CallbackGuard};
+use super::Result;
+
+/// A thread of an attached process or core file, as visited by
+/// `Dwfl::for_each_thread`.
+///
+/// Threads are only valid during the callback that visits them.
+pub struct Thread<'a> {
+    inner: *mut ffi::Dwfl_Thread,
+    phantom: PhantomData<&'a ffi::Dwfl_Thread>,
+}
+
+impl<'a> fmt::Debug for Thread<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Thread").field("tid", &self.tid()).finish()
+    }
+}
+
+impl<'a> Thread<'a> {
+    #[inline]
+    pub(crate) fn new(thread: *mut ffi::Dwfl_Thread) -> Thread<'a> {
+        Thread {
+            inner: thread,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Get the thread ID.
+    #[inline]
+    pub fn tid(&self) -> libc::pid_t {
+        raw_ffi!(dwfl_thread_tid(self.as_ptr()))
+    }
+
+    /// Unwind the stack of this thread, visiting each frame from the innermost.
+    ///
+    /// Frames visited before an unwinding error are still passed to `f`, and
+    /// then the error is returned.
+    pub fn for_each_frame<F>(&self, mut f: F) -> Result<()>
+    where
+        F: FnMut(&Frame<'_>) -> Result<bool>,
+    {
+        let mut guard = CallbackGuard::new();
+        let mut result = Ok(());
+
+        unsafe {
+            self.getframes(|frame| {
+                guard.call(ffi::DWARF_CB_ABORT, || dwarf_cb_map(f(frame), &mut result))
+            })?;
+        }
+
+        result
+    }
+
+    unsafe fn getframes<F>(&self, mut f: F) -> Result<libc::c_int>
+    where
+        F: FnMut(&Frame<'_>) -> libc::c_uint,
+    {
+        let argp = &mut f as *mut F as *mut libc::c_void;
+        ffi!(dwfl_thread_getframes(
+            self.as_ptr(),
+            Some(frame_callback::<F>),
+            argp
+        ))
+    }
+
+    #[inline]
+    pub fn as_ptr(&self) -> *mut ffi::Dwfl_Thread {
+        self.inner
+    }
+}
+
+/// A stack frame of a thread, as visited by `Thread::for_each_frame`.
+///
+/// Frames are only valid during the callback that visits them.
+pub struct Frame<'a> {
+    inner: *mut ffi::Dwfl_Frame,
+    phantom: PhantomData<&'a ffi::Dwfl_Frame>,
+}
+
+impl<'a> fmt::Debug for Frame<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("Frame");
+        match self.pc() {
+            Ok(pc) => s.field("pc", &format_args!("{:#x}", pc)),
+            Err(_) => s.field("pc", &"?"),
+        };
+        s.field("activation", &self.is_activation().unwrap_or(false))
+            .finish()
+    }
+}
+
+impl<'a> Frame<'a> {
+    #[inline]
+    fn new(frame: *mut ffi::Dwfl_Frame) -> Frame<'a> {
+        Frame {
+            inner: frame,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Get the thread of this frame.
+    #[inline]
+    pub fn thread(&self) -> Thread<'_> {
+        Thread::new(raw_ffi!(dwfl_frame_thread(self.as_ptr())))
+    }
+
+    /// Get the program counter of this frame.
+    #[inline]
+    pub fn pc(&self) -> Result<u64> {
+        Ok(self.pc_info()?.0)
+    }
+
+    /// Check whether this frame is an activation, which was interrupted at its
+    /// program counter, rather than a caller which will return to it.
+    ///
+    /// The innermost frame and signal frames are activations.
+    #[inline]
+    pub fn is_activation(&self) -> Result<bool> {
+        Ok(self.pc_info()?.1)
+    }
+
+    /// Get the address to use when symbolizing this frame.
+    ///
+    /// For a caller this is one before its return address, so it falls within
+    /// the call instruction rather than whatever follows it.
+    #[inline]
+    pub fn lookup_pc(&self) -> Result<u64> {
+        let (pc, activation) = self.pc_info()?;
+        Ok(if activation { pc } else { pc.wrapping_sub(1) })
+    }
+
+    /// Get the value of a register in this frame, by its DWARF register number.
+    ///
+    /// Returns `None` if the register's value is unknown, like a
+    /// caller-saved register in an unwound frame.
+    #[inline]
+    pub fn register(&self, regno: u32) -> Result<Option<u64>> {
+        let mut value = 0;
+        let rc = ffi!(dwfl_frame_reg(self.as_ptr(), regno, &mut value))?;
+        Ok(if rc == 0 { Some(value) } else { None })
+    }
+
+    fn pc_info(&self) -> Result<(u64, bool)> {
+        let mut pc = 0;
+        let mut activation = false;
+        if raw_ffi!(dwfl_frame_pc(self.as_ptr(), &mut pc, &mut activation)) {
+            Ok((pc, activation))
+        } else {
+            Err(crate::Error::last())
+        }
+    }
+
+    #[inline]
+    pub fn as_ptr(&self) -> *mut ffi::Dwfl_Frame {
+        self.inner
+    }
+}
+
+/// Pass each `Dwfl_Frame` to a Rust callback given as `argp`.
+pub(crate) unsafe extern "C" fn frame_callback<F>(
+    frame: *mut ffi::Dwfl_Frame,
+    argp: *mut libc::c_void,
+) -> libc::c_int
+where
+    F: FnMut(&Frame<'_>) -> libc::c_uint,
+{
+    let f = &mut *(argp as *mut F);
+    f(&Frame::new(frame)) as libc::c_int
+}
+
+/// Callbacks to supply the threads, memory and registers of a process for
+/// unwinding, as attached by `Dwfl::attach_state`.
+///
+/// This allows unwinding from a snapshot of registers and stack memory, like
+/// a profiler sample, without `ptrace` or a core file.  The callbacks are
+/// dropped when the `Dwfl` ends, in place of a `detach` callback.
+pub trait ThreadCallbacks {
+    /// Get the ID of the thread after `previous`, or of the first thread if
+    /// `previous` is `None`.  Returns `None` when there are no more threads.
+    fn next_thread(&mut self, previous: Option<libc::pid_t>) -> Option<libc::pid_t>;
+
+    /// Check whether a thread exists.
+    ///
+    /// By default this searches the threads from `next_thread`.
+    fn get_thread(&mut self, tid: libc::pid_t) -> bool {
+        let mut next = self.next_thread(None);
+        while let Some(thread) = next {
+            if thread == tid {
+                return true;
+            }
+            next = self.next_thread(Some(thread));
+        }
+        false
+    }
+
+    /// Read a word of the process's address size from memory, or `None` if the
+    /// address is not available.
+    fn memory_read(&mut self, address: u64) -> Option<u64>;
+
+    /// Set the registers of a thread where unwinding starts, returning `false`
+    /// if they are not available.
+    fn set_initial_registers(&mut self, registers: &ThreadRegisters<'_>) -> bool;
+
+    /// Release any resources of a thread after it has been unwound.
+    fn thread_detach(&mut self, _tid: libc::pid_t) {}
+}
+
+/// The registers of a thread to set in `ThreadCallbacks::set_initial_registers`.
+pub struct ThreadRegisters<'a> {
+    inner: *mut ffi::Dwfl_Thread,
+    phantom: PhantomData<&'a ffi::Dwfl_Thread>,
+}
+
+impl<'a> ThreadRegisters<'a> {
+    /// Get the ID of the thread.
+    #[inline]
+    pub fn tid(&self) -> libc::pid_t {
+        raw_ffi!(dwfl_thread_tid(self.inner))
+    }
+
+    /// Set the values of consecutive registers, by DWARF register number.
+    pub fn set(&self, first_regno: u32, values: &[u64]) -> Result<()> {
+        if raw_ffi!(dwfl_thread_state_registers(
+            self.inner,
+            first_regno as libc::c_int,
+            values.len() as libc::c_uint,
+            values.as_ptr()
+        )) {
+            Ok(())
+        } else {
+            Err(crate::Error::last())
+        }
+    }
+
+    /// Set the program counter, for architectures where it has no DWARF
+    /// register number.
+    #[inline]
+    pub fn set_pc(&self, pc: u64) {
+        raw_ffi!(dwfl_thread_state_register_pc(self.inner, pc));
+    }
+}
+
+/// The state given to libdwfl for `ThreadCallbacks`.
+///
+/// Each thread's own argument is just its ID, so that concurrent walks over
+/// the threads each keep their place.
+pub(crate) struct CallbackState<T> {
+    table: ffi::Dwfl_Thread_Callbacks,
+    callbacks: T,
+}
+
+thread_local! {
+    // The `CallbackState` of each `Dwfl`, for the callbacks that only get a
+    // thread.  A `Dwfl` is not `Send`, so its callbacks run on this thread.
+    static STATES: RefCell<HashMap<usize, *mut libc::c_void>> = RefCell::default();
+}
+
+impl<T: ThreadCallbacks> CallbackState<T> {
+    pub(crate) fn new(callbacks: T) -> Box<Self> {
+        Box::new(CallbackState {
+            table: ffi::Dwfl_Thread_Callbacks {
+                next_thread: Some(next_thread::<T>),
+                get_thread: Some(get_thread::<T>),
+                memory_read: Some(memory_read::<T>),
+                set_initial_registers: Some(set_initial_registers::<T>),
+                detach: Some(detach::<T>),
+                thread_detach: Some(thread_detach::<T>),
+            },
+            callbacks,
+        })
+    }
+
+    #[inline]
+    pub(crate) fn table(&self) -> *const ffi::Dwfl_Thread_Callbacks {
+        &self.table
+    }
+
+    /// Make `state` the thread callback state of `dwfl`.
+    pub(crate) fn register(dwfl: *mut ffi::Dwfl, state: *mut Self) {
+        STATES.with(|states| states.borrow_mut().insert(dwfl as usize, state.cast()));
+    }
+
+    /// Forget the thread callback state of `dwfl`.
+    pub(crate) fn unregister(dwfl: *mut ffi::Dwfl) {
+        STATES.with(|states| states.borrow_mut().remove(&(dwfl as usize)));
+    }
+
+    /// Get the state of the `Dwfl` that `thread` belongs to.
+    unsafe fn of_thread<'a>(thread: *mut ffi::Dwfl_Thread) -> &'a mut Self {
+        let dwfl = raw_ffi!(dwfl_thread_dwfl(thread)) as usize;
+        let state = STATES.with(|states| states.borrow()[&dwfl]);
+        &mut *(state as *mut Self)
+    }
+}
+
+/// Encode a thread ID as a libdwfl thread argument.
+#[inline]
+fn thread_arg(tid: libc::pid_t) -> *mut libc::c_void {
+    tid as usize as *mut libc::c_void
+}
+
+unsafe extern "C" fn next_thread<T: ThreadCallbacks>(
+    _dwfl: *mut ffi::Dwfl,
+    arg: *mut libc::c_void,
+    thread_argp: *mut *mut libc::c_void,
+) -> libc::pid_t {
+    catch_panic(-1, || {
+        let state = &mut *(arg as *mut CallbackState<T>);
+        // libdwfl starts each search with a null thread argument, and keeps
+        // the previous thread's argument from there.
+        let previous = if (*thread_argp).is_null() {
+            None
+        } else {
+            Some(*thread_argp as usize as libc::pid_t)
+        };
+        match state.callbacks.next_thread(previous) {
+            Some(tid) if tid > 0 => {
+                *thread_argp = thread_arg(tid);
+                tid
+            }
+            _ => 0,
+        }
+    })
+}
+
+unsafe extern "C" fn get_thread<T: ThreadCallbacks>(
+    _dwfl: *mut ffi::Dwfl,
+    tid: libc::pid_t,
+    arg: *mut libc::c_void,
+    thread_argp: *mut *mut libc::c_void,
+) -> bool {
+    catch_panic(false, || {
+        let state = &mut *(arg as *mut CallbackState<T>);
+        *thread_argp = thread_arg(tid);
+        state.callbacks.get_thread(tid)
+    })
+}
+
+unsafe extern "C" fn memory_read<T: ThreadCallbacks>(
+    _dwfl: *mut ffi::Dwfl,
+    address: ffi::Dwarf_Addr,
+    result: *mut ffi::Dwarf_Word,
+    arg: *mut libc::c_void,
+) -> bool {
+    catch_panic(false, || {
+        let state = &mut *(arg as *mut CallbackState<T>);
+        match state.callbacks.memory_read(address) {
+            Some(value) => {
+                *result = value;
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+unsafe extern "C" fn set_initial_registers<T: ThreadCallbacks>(
+    thread: *mut ffi::Dwfl_Thread,
+    _thread_arg: *mut libc::c_void,
+) -> bool {
+    catch_panic(false, || {
+        let state = CallbackState::<T>::of_thread(thread);
+        let registers = ThreadRegisters {
+            inner: thread,
+            phantom: PhantomData,
+        };
+        state.callbacks.set_initial_registers(&registers)
+    })
+}
+
+unsafe extern "C" fn detach<T: ThreadCallbacks>(dwfl: *mut ffi::Dwfl, arg: *mut libc::c_void) {
+    catch_panic((), || {
+        CallbackState::<T>::unregister(dwfl);
+        drop(Box::from_raw(arg as *mut CallbackState<T>))
+    })
+}
+
+unsafe extern "C" fn thread_detach<T: ThreadCallbacks>(
+    thread: *mut ffi::Dwfl_Thread,
+    _thread_arg: *mut libc::c_void,
+) {
+    catch_panic((), || {
+        let state = CallbackState::<T>::of_thread(thread);
+        state
+            .callbacks
+            .thread_detach(raw_ffi!(dwfl_thread_tid(thread)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ThreadCallbacks, ThreadRegisters};
+    use crate::Dwfl;
+    use std::process::Command;
+    use std::{fs, thread, time};
+
+    #[test]
+    fn child_frames() {
+        let mut child = Command::new("sleep").arg("60").spawn().unwrap();
+        let pid = child.id() as libc::pid_t;
+
+        // Wait for the child to exec and fall asleep, so we don't attach to
+        // our own fork or a half-loaded process.
+        let exe = fs::read_link("/proc/self/exe").unwrap();
+        let stat = format!("/proc/{}/stat", pid);
+        while fs::read_link(format!("/proc/{}/exe", pid)).unwrap() == exe
+            || !fs::read_to_string(&stat).unwrap().contains(") S ")
+        {
+            thread::sleep(time::Duration::from_millis(10));
+        }
+
+        let mut dwfl = Dwfl::from_pid(pid).unwrap();
+        dwfl.attach_pid(pid, false).unwrap();
+        assert_eq!(dwfl.pid(), Some(pid));
+        assert_eq!(dwfl.threads().unwrap(), [pid]);
+
+        let mut frames = Vec::new();
+        let _ = dwfl.for_each_thread_frame(pid, |frame| {
+            assert_eq!(frame.thread().tid(), pid);
+            frames.push((frame.pc()?, frame.is_activation()?));
+            Ok(true)
+        });
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        assert!(frames.len() > 1);
+        assert!(frames[0].1);
+        assert!(!frames[1].1);
+    }
+
+    /// Threads that exist in name only.
+    struct FakeThreads(Vec<libc::pid_t>);
+
+    impl ThreadCallbacks for FakeThreads {
+        fn next_thread(&mut self, previous: Option<libc::pid_t>) -> Option<libc::pid_t> {
+            let index = match previous {
+                None => 0,
+                Some(tid) => self.0.iter().position(|&t| t == tid)? + 1,
+            };
+            self.0.get(index).cloned()
+        }
+
+        fn memory_read(&mut self, _address: u64) -> Option<u64> {
+            None
+        }
+
+        fn set_initial_registers(&mut self, _registers: &ThreadRegisters<'_>) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn nested_threads() {
+        let pid = std::process::id() as libc::pid_t;
+        let mut dwfl = Dwfl::from_pid(pid).unwrap();
+        dwfl.attach_state(None, pid, FakeThreads(vec![1, 2, 3]))
+            .unwrap();
+
+        // Each walk keeps its own place among the threads.
+        let mut tids = Vec::new();
+        dwfl.for_each_thread(|thread| {
+            assert_eq!(dwfl.threads()?, [1, 2, 3]);
+            tids.push(thread.tid());
+            Ok(true)
+        })
+        .unwrap();
+        assert_eq!(tids, [1, 2, 3]);
+    }
+
+    /// Threads whose callbacks panic when dropped.
+    struct PanickyThreads(FakeThreads);
+
+    impl ThreadCallbacks for PanickyThreads {
+        fn next_thread(&mut self, previous: Option<libc::pid_t>) -> Option<libc::pid_t> {
+            self.0.next_thread(previous)
+        }
+
+        fn memory_read(&mut self, address: u64) -> Option<u64> {
+            self.0.memory_read(address)
+        }
+
+        fn set_initial_registers(&mut self, registers: &ThreadRegisters<'_>) -> bool {
+            self.0.set_initial_registers(registers)
+        }
+    }
+
+    impl Drop for PanickyThreads {
+        fn drop(&mut self) {
+            panic!("dropped");
+        }
+    }
+
+    #[test]
+    fn panic_in_drop() {
+        let pid = std::process::id() as libc::pid_t;
+        let mut dwfl = Dwfl::from_pid(pid).unwrap();
+        dwfl.attach_state(None, pid, PanickyThreads(FakeThreads(vec![1])))
+            .unwrap();
+
+        // Dropping the `Dwfl` drops the callbacks, and must not panic itself.
+        drop(dwfl);
+        assert!(Dwfl::from_pid(pid).is_ok());
+    }
+
+    /// A copy of registers and stack memory, as a profiler might sample.
+    #[cfg(target_arch = "x86_64")]
+    struct Snapshot {
+        tid: libc::pid_t,
+        registers: [u64; 17],
+        stack_start: u64,
+        stack: Vec<u8>,
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    impl ThreadCallbacks for Snapshot {
+        fn next_thread(&mut self, previous: Option<libc::pid_t>) -> Option<libc::pid_t> {
+            match previous {
+                None => Some(self.tid),
+                Some(_) => None,
+            }
+        }
+
+        fn memory_read(&mut self, address: u64) -> Option<u64> {
+            let offset = address.checked_sub(self.stack_start)? as usize;
+            let bytes = self.stack.get(offset..offset + 8)?;
+            Some(u64::from_ne_bytes(bytes.try_into().unwrap()))
+        }
+
+        fn set_initial_registers(&mut self, registers: &ThreadRegisters<'_>) -> bool {
+            assert_eq!(registers.tid(), self.tid);
+            // Only the frame registers are sampled, leaving the rest unknown.
+            registers.set(6, &self.registers[6..8]).is_ok()
+                && registers.set(16, &self.registers[16..]).is_ok()
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[inline(never)]
+    fn snapshot() -> Snapshot {
+        let (rip, rsp, rbp): (u64, u64, u64);
+        unsafe {
+            std::arch::asm!(
+                "lea {}, [rip]",
+                "mov {}, rsp",
+                "mov {}, rbp",
+                out(reg) rip,
+                out(reg) rsp,
+                out(reg) rbp,
+            );
+        }
+
+        // Copy the stack from `rsp` to the end of its mapping.
+        let maps = fs::read_to_string("/proc/self/maps").unwrap();
+        let end = maps
+            .lines()
+            .filter_map(|line| {
+                let range = line.split(' ').next()?;
+                let (start, end) = range.split_once('-')?;
+                let start = u64::from_str_radix(start, 16).ok()?;
+                let end = u64::from_str_radix(end, 16).ok()?;
+                (start..end).contains(&rsp).then_some(end)
+            })
+            .next()
+            .unwrap();
+        let stack = unsafe { std::slice::from_raw_parts(rsp as *const u8, (end - rsp) as usize) };
+
+        // DWARF numbers rbp as 6, rsp as 7, and rip as 16.
+        let mut registers = [0; 17];
+        registers[6] = rbp;
+        registers[7] = rsp;
+        registers[16] = rip;
+        Snapshot {
+            tid: unsafe { libc::gettid() },
+            registers,
+            stack_start: rsp,
+            stack: stack.to_vec(),
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn snapshot_frames() {
+        let snapshot = snapshot();
+        let tid = snapshot.tid;
+        let rip = snapshot.registers[16];
+        let rsp = snapshot.registers[7];
+
+        let pid = std::process::id() as libc::pid_t;
+        let mut dwfl = Dwfl::from_pid(pid).unwrap();
+        dwfl.attach_state(None, pid, snapshot).unwrap();
+        assert_eq!(dwfl.pid(), Some(pid));
+        assert_eq!(dwfl.threads().unwrap(), [tid]);
+
+        let mut pcs = Vec::new();
+        let mut registers = Vec::new();
+        let _ = dwfl.for_each_thread_frame(tid, |frame| {
+            pcs.push(frame.lookup_pc()?);
+            registers.push((frame.register(7)?, frame.register(0)?));
+            Ok(true)
+        });
+        assert_eq!(pcs[0], rip);
+        assert_eq!(registers[0], (Some(rsp), None));
+        let caller = dwfl.symbolize(pcs[1]).unwrap().unwrap();
+        let name = rustc_demangle::demangle(caller.name().to_str().unwrap()).to_string();
+        assert!(name.contains("snapshot_frames"), "{}", name);
+    }
+}